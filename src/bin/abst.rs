@@ -66,7 +66,7 @@ async fn main() {
     info!("{:?}", abs_state_2);
 
     if bal_other < 2.0 {
-        _ = executor
+        executor
             .send_asset_to_dex(SendAssetRequest {
                 sig_chain_id: "0xa4b1".to_string(),
                 chain: Network::Mainnet.name(),
@@ -99,7 +99,7 @@ async fn main() {
     let info = executor.get_perp_info(Some("xyz".into())).await.unwrap();
     let mid_px: f64 = info
         .1
-        .get(0)
+        .first()
         .unwrap()
         .mid_px
         .clone()
@@ -107,7 +107,7 @@ async fn main() {
         .parse()
         .unwrap();
 
-    let sz = (lev * double_margin / mid_px);
+    let sz = lev * double_margin / mid_px;
 
     let resp = executor
         .create_position_raw(BulkOrder {
@@ -122,7 +122,8 @@ async fn main() {
                 order_type: OrderType::Limit(Limit { tif: "Ioc".into() }),
                 cloid: None,
             }],
-            grouping: "na".into(),
+            grouping: Grouping::Na,
+            builder: None,
         })
         .await
         .unwrap();
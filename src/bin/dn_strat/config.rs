@@ -16,4 +16,13 @@ pub struct Config {
 
     #[envconfig(from = "BIND_ADDR")]
     pub bind_addr: String,
+
+    /// sub-account or led vault to run the strategy in, positions are read from and orders placed for it
+    #[envconfig(from = "SUB_ACCOUNT")]
+    pub sub_account: Option<String>,
+
+    /// when set, open orders are cancelled this many seconds after the bot stops heartbeating,
+    /// at least 5
+    #[envconfig(from = "DEAD_MAN_SWITCH_SECS")]
+    pub dead_man_switch_secs: Option<u64>,
 }
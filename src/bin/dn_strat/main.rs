@@ -6,7 +6,7 @@ use envconfig::Envconfig;
 use hyperqit::*;
 use tokio::signal;
 use tokio_util::sync::CancellationToken;
-use tracing::info;
+use tracing::{error, info};
 
 mod config;
 mod handlers;
@@ -35,19 +35,35 @@ async fn main() {
 
     let user_address: Address = config.user_address.parse().unwrap();
 
-    let executor = crate::HyperliquidClient::new(Network::Testnet, Box::new(signer), user_address);
+    // a key other than the account's own is an approved agent trading on its behalf
+    let is_master = signer.address() == user_address;
+    // spot to perp transfers move the master's own USDC, which agents cannot sign for and
+    // sub-accounts do not trade on
+    let margin_top_up = is_master && config.sub_account.is_none();
+    let executor = if is_master {
+        crate::HyperliquidClient::new(Network::Testnet, Box::new(signer), user_address)
+    } else {
+        crate::HyperliquidClient::new_agent(Network::Testnet, Box::new(signer), user_address)
+    };
+    let executor = Arc::new(match config.sub_account {
+        Some(sub_account) => executor.for_vault_or_subaccount(sub_account.parse().unwrap()),
+        None => executor,
+    });
     let asset = Asset::CommonAsset("HYPE".to_owned());
     let notifier = NotifierService::new(config.bot_url, user_address.to_string());
-    let strategy = Arc::new(Strategy::new(
-        1,
-        Duration::from_secs(config.check_every),
-        asset.clone(),
-        0.005,
-        0.1f64,
-        0.7,
-        executor,
-        notifier,
-    ));
+    let strategy = Arc::new(
+        Strategy::new(
+            1,
+            Duration::from_secs(config.check_every),
+            asset.clone(),
+            0.005,
+            0.1f64,
+            0.7,
+            executor.clone(),
+            notifier,
+        )
+        .with_margin_top_up(margin_top_up),
+    );
 
     let strategy_manager = Arc::new(StrategyManagerService::new(
         strategy.clone(),
@@ -62,6 +78,21 @@ async fn main() {
     info!("server running on http://{}", config.bind_addr);
 
     let cancellation = CancellationToken::new();
+    let dead_man_switch = config.dead_man_switch_secs.map(|secs| {
+        let timeout = Duration::from_secs(secs);
+        let handle = executor
+            .clone()
+            .spawn_dead_man_switch(timeout, timeout / 3, cancellation.clone())
+            .unwrap();
+        // trading on without the switch armed defeats its purpose, shut down instead
+        let switch_cancellation = cancellation.clone();
+        tokio::spawn(async move {
+            if let Ok(Err(e)) = handle.await {
+                error!("dead man switch failed, shutting down: {}", e);
+                switch_cancellation.cancel();
+            }
+        })
+    });
     let strategy_for_runner = strategy.clone();
     let server_cancellation = cancellation.clone();
     let runner_cancellation = cancellation.clone();
@@ -105,5 +136,8 @@ async fn main() {
     });
 
     let _ = tokio::join!(server_handle, runner_handle);
+    if let Some(handle) = dead_man_switch {
+        let _ = handle.await;
+    }
     info!("graceful shutdown complete");
 }
@@ -9,6 +9,7 @@ use crate::{
     },
 };
 
+use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
 use std::result::Result::Ok;
@@ -16,6 +17,19 @@ use tokio::time;
 use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
+/// A top-up aims to put the mark at this fraction of `liq_threshold` of the liquidation price
+const TOP_UP_TARGET: f64 = 0.9;
+/// chain the user-signed spot to perp transfers are signed for
+const SIG_CHAIN_ID: &str = "0x66eee";
+
+#[derive(Debug, Clone, PartialEq)]
+enum HealthDecision {
+    Hold,
+    /// move this much spot USDC into the perp leg
+    TopUp(f64),
+    Exit,
+}
+
 pub struct Strategy {
     asset: Asset,
     slippage: f64,
@@ -23,7 +37,8 @@ pub struct Strategy {
     leverage: u32,
     dust_threshold: f64,
     tick_interval: Duration,
-    executor: HyperliquidClient,
+    margin_top_up: bool,
+    executor: Arc<HyperliquidClient>,
     notifier: NotifierService,
 }
 
@@ -35,7 +50,7 @@ impl Strategy {
         slippage: f64,
         dust_threshold: f64,
         liq_threshold: f64,
-        executor: HyperliquidClient,
+        executor: Arc<HyperliquidClient>,
         notifier: NotifierService,
     ) -> Self {
         Strategy {
@@ -45,11 +60,20 @@ impl Strategy {
             liq_threshold,
             tick_interval,
             dust_threshold,
+            margin_top_up: true,
             executor,
             notifier,
         }
     }
 
+    /// Whether a position near liquidation is topped up with spot USDC before giving up on it.
+    /// Transfers are signed by the master key for its own account, so agents and sub-accounts
+    /// have to exit instead.
+    pub fn with_margin_top_up(mut self, enabled: bool) -> Self {
+        self.margin_top_up = enabled;
+        self
+    }
+
     pub async fn info(&self) -> Result<()> {
         match self.state().await {
             Ok(state) => match state.status {
@@ -79,13 +103,13 @@ impl Strategy {
         Ok(())
     }
 
-    async fn check_health(&self) -> Result<bool> {
+    async fn check_health(&self) -> Result<HealthDecision> {
         let (perp_info, _) = self.get_market_data().await?;
         let user_state = self.state().await?;
 
         if user_state.status == StrategyStatus::InActive {
             info!("check: strategy inactive, no action needed");
-            return Ok(false);
+            return Ok(HealthDecision::Hold);
         }
 
         let current_funding_rate: f64 = perp_info.funding.parse()?;
@@ -98,7 +122,7 @@ impl Strategy {
             );
             info!("{}", check);
             self.notifier.notify("check", &check).await;
-            return Ok(true);
+            return Ok(HealthDecision::Exit);
         }
 
         let current_mark_px: f64 = perp_info.mark_px.parse()?;
@@ -114,20 +138,77 @@ impl Strategy {
             self.liq_threshold * 100.0
         );
 
-        let should_exit = price_ratio >= self.liq_threshold;
-        if should_exit {
-            let check = format!(
-                "decision: price {:.1}% of liq price exceeds threshold {:.1}%, exiting position",
-                price_ratio * 100.0,
-                self.liq_threshold * 100.0
-            );
-            info!("{}", check);
-            self.notifier.notify("check", &check).await;
-        } else {
+        if price_ratio < self.liq_threshold {
             info!("decision: conditions good, maintaining position");
+            return Ok(HealthDecision::Hold);
         }
 
-        Ok(should_exit)
+        if self.margin_top_up {
+            // the perp leg is short, its liquidation price sits above the mark and rises with
+            // every USDC of margin added
+            let target_liq_px = current_mark_px / (self.liq_threshold * TOP_UP_TARGET);
+            let needed = (target_liq_px - user_pos.liq_px) * user_pos.perp_amount.abs();
+            let available = self.spot_usdc_available().await?;
+            if needed.ceil() <= available {
+                let check = format!(
+                    "decision: price {:.1}% of liq price exceeds threshold {:.1}%, topping up perp margin with {:.2} USDC",
+                    price_ratio * 100.0,
+                    self.liq_threshold * 100.0,
+                    needed
+                );
+                info!("{}", check);
+                self.notifier.notify("check", &check).await;
+                return Ok(HealthDecision::TopUp(needed));
+            }
+            info!(
+                "check: top up needs {:.2} USDC, only {:.2} available on spot",
+                needed, available
+            );
+        }
+
+        let check = format!(
+            "decision: price {:.1}% of liq price exceeds threshold {:.1}%, exiting position",
+            price_ratio * 100.0,
+            self.liq_threshold * 100.0
+        );
+        info!("{}", check);
+        self.notifier.notify("check", &check).await;
+        Ok(HealthDecision::Exit)
+    }
+
+    async fn spot_usdc_available(&self) -> Result<f64> {
+        let user_spot = self.executor.get_user_spot_info(None).await?;
+        let Some(usdc) = user_spot.balances.iter().find(|item| item.coin == "USDC") else {
+            return Ok(0.0);
+        };
+        Ok(usdc.total.parse::<f64>()? - usdc.hold.parse::<f64>()?)
+    }
+
+    /// Moves `usdc` (rounded up to whole dollars) from spot into the perp leg's margin
+    async fn top_up(&self, usdc: f64) -> Result<()> {
+        let (perp_info, _) = self.get_market_data().await?;
+        let amount = usdc.ceil() as u64;
+        self.executor
+            .transfer_usd(amount, true, SIG_CHAIN_ID.to_owned())
+            .await?;
+
+        // a cross position draws on the whole perp balance, an isolated one has to be handed it
+        let user_perp = self.executor.get_user_perp_info(None).await?;
+        if let Some(perp_pos) = user_perp
+            .asset_positions
+            .iter()
+            .find(|item| item.position.coin == perp_info.name)
+            && perp_pos.position.leverage.type_field == "isolated"
+        {
+            self.executor
+                .update_isolated_margin(perp_info.asset_id, false, amount as i64 * 1_000_000)
+                .await?;
+        }
+
+        self.notifier
+            .notify_text("top up", &format!("moved {} USDC to perp", amount))
+            .await;
+        Ok(())
     }
 
     pub async fn run(&self, cancellation: CancellationToken) -> Result<()> {
@@ -139,17 +220,28 @@ impl Strategy {
                 _ = ticker.tick() => {
                     let _ = self.info().await;
 
-                    match self.check_health().await {
-                        Ok(should_exit) => {
-                            if should_exit {
-                                match self.exit().await {
-                                    Ok(_) => info!("exited position"),
-                                    Err(e) => warn!("exit failed: {}", e),
-                                }
+                    let should_exit = match self.check_health().await {
+                        Ok(HealthDecision::Hold) => false,
+                        Ok(HealthDecision::TopUp(usdc)) => match self.top_up(usdc).await {
+                            Ok(_) => {
+                                info!("topped up perp margin");
+                                false
                             }
-                        }
+                            Err(e) => {
+                                warn!("top up failed, exiting instead: {}", e);
+                                true
+                            }
+                        },
+                        Ok(HealthDecision::Exit) => true,
                         Err(e) => {
                             warn!("health check failed: {}", e);
+                            false
+                        }
+                    };
+                    if should_exit {
+                        match self.exit().await {
+                            Ok(_) => info!("exited position"),
+                            Err(e) => warn!("exit failed: {}", e),
                         }
                     }
                 }
@@ -463,3 +555,95 @@ impl Strategy {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::strategy::Asset;
+    use hyperqit::{
+        LocalWallet,
+        testing::{MockExchange, MockServer},
+    };
+
+    async fn mock_strategy(liq_threshold: f64) -> (MockServer, Strategy) {
+        let wallet = LocalWallet::signer(
+            "0x0123456789012345678901234567890123456789012345678901234567890123".into(),
+        );
+        let user = wallet.address();
+        let server = MockExchange::new(user)
+            .with_perp("ETH", 2000.0, 4)
+            .with_spot("UETH", 2000.0, 4)
+            .with_perp_balance(1000.0)
+            .with_spot_balance("USDC", 1000.0)
+            .serve()
+            .await
+            .unwrap();
+        let executor = Arc::new(HyperliquidClient::new(
+            server.network(),
+            Box::new(wallet),
+            user,
+        ));
+        let strategy = Strategy::new(
+            2,
+            Duration::from_secs(60),
+            Asset::WithPerpAndSpot("ETH".into(), "UETH".into()),
+            0.01,
+            0.0001,
+            liq_threshold,
+            executor,
+            NotifierService::new(String::new(), user.to_string()),
+        );
+        (server, strategy)
+    }
+
+    #[tokio::test]
+    async fn test_enter_and_exit_against_mock_exchange() {
+        let (server, strategy) = mock_strategy(0.1).await;
+
+        strategy.enter(Amount::Raw("0.2".into())).await.unwrap();
+        let state = strategy.state().await.unwrap();
+        assert_eq!(state.status, StrategyStatus::Active);
+        let perp = &state.position.as_ref().unwrap();
+        assert_eq!(perp.perp_amount, -0.2);
+        assert_eq!(perp.spot_amount, 0.2);
+
+        strategy.exit().await.unwrap();
+        assert_eq!(
+            strategy.state().await.unwrap().status,
+            StrategyStatus::InActive
+        );
+        assert_eq!(server.fills().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_top_up_before_exit_near_liquidation() {
+        let (server, strategy) = mock_strategy(0.7).await;
+        // 0.2 ETH short on 1000 USDC of cross margin liquidates around 7000
+        strategy.enter(Amount::Raw("0.2".into())).await.unwrap();
+        assert_eq!(strategy.check_health().await.unwrap(), HealthDecision::Hold);
+
+        server.set_mark_px("ETH", 5000.0);
+        let HealthDecision::TopUp(usdc) = strategy.check_health().await.unwrap() else {
+            panic!("expected a top up");
+        };
+        let spot_before = strategy.spot_usdc_available().await.unwrap();
+        strategy.top_up(usdc).await.unwrap();
+        assert_eq!(
+            strategy.spot_usdc_available().await.unwrap(),
+            spot_before - usdc.ceil()
+        );
+        assert_eq!(strategy.check_health().await.unwrap(), HealthDecision::Hold);
+
+        // what is left on spot no longer covers the move, the position has to go
+        server.set_mark_px("ETH", 6900.0);
+        assert_eq!(strategy.check_health().await.unwrap(), HealthDecision::Exit);
+        assert_eq!(
+            strategy
+                .with_margin_top_up(false)
+                .check_health()
+                .await
+                .unwrap(),
+            HealthDecision::Exit
+        );
+    }
+}
@@ -45,7 +45,7 @@ async fn main() {
     println!("{:?}", signer.address());
     let executor = crate::HyperliquidClient::new(Network::Testnet, signer, user_address);
 
-    let sz_decimals = 0;
+    let _sz_decimals = 0;
     // executor
     //     .perp_deploy_action(PerpDeployAction::RegisterAsset(RegisterAsset {
     //         max_gas: None,
@@ -1,8 +1,15 @@
 use alloy::sol_types::SolStruct;
 use anyhow::Ok;
+use futures::Stream;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
 
-use std::time::SystemTime;
-use tracing::{debug, error, info};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
 
 use alloy::primitives::{Address, FixedBytes};
 
@@ -15,167 +22,447 @@ use crate::signing::*;
 use crate::user_data::*;
 use crate::utils::*;
 use crate::wallet::*;
+use crate::ws::HyperliquidWsClient;
 
 pub struct HyperliquidClient {
     client: reqwest::Client,
     signer: Box<dyn Signer + Send + Sync>,
     network: Network,
     user: Address,
-    nonce_manager: NonceManager,
+    nonce_manager: Arc<NonceManager>,
+    ws: Option<HyperliquidWsClient>,
+    is_agent: bool,
+    vault_address: Option<Address>,
 }
 
 impl HyperliquidClient {
+    /// Nonces come from the manager shared by every client of the signer's key, or of `user`
+    /// when the signer does not know its address.
     pub fn new(network: Network, signer: Box<dyn Signer + Send + Sync>, user: Address) -> Self {
         debug!("creating hyperliquid client for {} on {:?}", user, network);
+        let nonce_manager = NonceManager::shared(signer.address().unwrap_or(user));
         HyperliquidClient {
             client: reqwest::Client::new(),
             signer,
             network,
             user,
-            nonce_manager: NonceManager::new(),
+            nonce_manager,
+            ws: None,
+            is_agent: false,
+            vault_address: None,
         }
     }
 
-    pub async fn get_dex_abstraction(&self) -> Result<Option<bool>> {
-        let req = GetUserDexAbstraction {
-            request_type: "userDexAbstraction".into(),
-            user: self.user.to_string(),
-        };
+    /// Trades on behalf of `master` with an agent key approved through
+    /// [`HyperliquidClient::approve_agent`]. Info queries and positions stay keyed by `master`,
+    /// user-signed actions (transfers, withdrawals, approvals) still need the master key and
+    /// fail with [`Errors::AgentCannotSign`].
+    pub fn new_agent(
+        network: Network,
+        agent: Box<dyn Signer + Send + Sync>,
+        master: Address,
+    ) -> Self {
+        HyperliquidClient {
+            is_agent: true,
+            ..Self::new(network, agent, master)
+        }
+    }
+
+    /// Route orders, cancels and info queries through `ws` as `post` messages instead of
+    /// opening a fresh http request for each call
+    pub fn with_ws(mut self, ws: HyperliquidWsClient) -> Self {
+        self.ws = Some(ws);
+        self
+    }
+
+    /// Draw nonces from another manager than the signer's shared one. Clients signing with the
+    /// same key must share it, otherwise they can hand out the same nonce and the exchange
+    /// rejects the later action. To persist nonces, prefer [`NonceManager::shared_persistent`]
+    /// which every client of the key picks up without this.
+    pub fn with_nonce_manager(mut self, nonce_manager: Arc<NonceManager>) -> Self {
+        self.nonce_manager = nonce_manager;
+        self
+    }
+
+    async fn info_request<R: Serialize, T: DeserializeOwned>(&self, req: &R) -> Result<T> {
+        if let Some(ws) = &self.ws {
+            return ws.post_info(req).await;
+        }
 
         let resp = self
             .client
-            .post(format!("{}/info", Into::<String>::into(self.network)))
+            .post(format!("{}/info", self.network.api_url()))
             .header("Content-Type", "application/json")
-            .json(&req)
+            .json(req)
             .send()
             .await?;
 
         let status_code = resp.status().as_u16();
         let body = resp.text().await?;
         if status_code != 200 {
-            error!("failed to get funding history: {} - {}", status_code, body);
+            error!("info request failed: {} - {}", status_code, body);
             return Err(Errors::HyperLiquidApiError(status_code, body).into());
         }
 
         Ok(serde_json::from_str(body.as_str())?)
     }
 
-    pub async fn get_user_funding_history(&self, since: u128) -> Result<FundingHistory> {
-        debug!(
-            "fetching funding history for user {} since {}",
-            self.user, since
-        );
+    /// Trades for a vault led by, or a sub-account of, `user`: L1 actions are signed for and
+    /// executed on `address`, and account info queries read its state instead of the signer's
+    pub fn for_vault_or_subaccount(mut self, address: Address) -> Self {
+        self.vault_address = Some(address);
+        self
+    }
 
-        let end_time = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)?
-            .as_millis();
+    /// account whose positions and orders this client manages
+    fn account(&self) -> Address {
+        self.vault_address.unwrap_or(self.user)
+    }
 
-        let req = GetUserFundingHistoryReq {
-            request_type: "userFunding".into(),
-            user: self.user.to_string(),
-            end_time,
-            start_time: end_time - since,
-        };
+    fn vault_param(&self) -> Option<String> {
+        self.vault_address
+            .map(|vault| vault.to_string().to_lowercase())
+    }
+
+    /// Signs the EIP-712 hash of a user-signed action, which only the master key may do
+    async fn sign_user_action(&self, action: &str, hash: FixedBytes<32>) -> Result<SignedMessage> {
+        if self.is_agent {
+            return Err(Errors::AgentCannotSign(action.into()).into());
+        }
+
+        self.signer.sign_order(hash).await
+    }
+
+    async fn exchange_request(&self, payload: &ExchangeRequest) -> Result<ExchangeResponse> {
+        if let Some(ws) = &self.ws {
+            return ws.post_action(payload).await;
+        }
 
         let resp = self
             .client
-            .post(format!("{}/info", Into::<String>::into(self.network)))
-            .header("Content-Type", "application/json")
-            .json(&req)
+            .post(format!("{}/exchange", self.network.api_url()))
+            .json(payload)
             .send()
             .await?;
 
         let status_code = resp.status().as_u16();
         let body = resp.text().await?;
         if status_code != 200 {
-            error!("failed to get funding history: {} - {}", status_code, body);
+            error!("exchange request failed: {} - {}", status_code, body);
             return Err(Errors::HyperLiquidApiError(status_code, body).into());
         }
 
         Ok(serde_json::from_str(body.as_str())?)
     }
 
+    pub async fn get_dex_abstraction(&self) -> Result<Option<bool>> {
+        let req = GetUserDexAbstraction {
+            request_type: "userDexAbstraction".into(),
+            user: self.account().to_string(),
+        };
+
+        self.info_request(&req).await
+    }
+
+    pub async fn get_user_funding_history(&self, since: u128) -> Result<FundingHistory> {
+        debug!(
+            "fetching funding history for user {} since {}",
+            self.account(),
+            since
+        );
+
+        let end_time = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_millis();
+
+        let req = GetUserFundingHistoryReq {
+            request_type: "userFunding".into(),
+            user: self.account().to_string(),
+            end_time,
+            start_time: end_time - since,
+        };
+
+        self.info_request(&req).await
+    }
+
     pub async fn get_user_open_orders(
         &self,
         dex: Option<String>,
     ) -> Result<UserOpenOrdersResponse> {
-        debug!("fetching open orders for user {}", self.user);
+        debug!("fetching open orders for user {}", self.account());
 
         let req = GetUserOpenOrders {
             request_type: "openOrders".into(),
-            user: self.user.to_string(),
+            user: self.account().to_string(),
             dex,
         };
 
-        let resp = self
-            .client
-            .post(format!("{}/info", Into::<String>::into(self.network)))
-            .header("Content-Type", "application/json")
-            .json(&req)
-            .send()
-            .await?;
-
-        let status_code = resp.status().as_u16();
-        let body = resp.text().await?;
-        if status_code != 200 {
-            error!("failed to get funding history: {} - {}", status_code, body);
-            return Err(Errors::HyperLiquidApiError(status_code, body).into());
-        }
-
-        Ok(serde_json::from_str(body.as_str())?)
+        self.info_request(&req).await
     }
 
     pub async fn get_user_history(&self) -> Result<UserOrderHistoryResponse> {
-        debug!("fetching historicalOrders orders for user {}", self.user);
+        debug!(
+            "fetching historicalOrders orders for user {}",
+            self.account()
+        );
 
         let req = GetHistoricalOrders {
             request_type: "historicalOrders".into(),
-            user: self.user.to_string(),
+            user: self.account().to_string(),
         };
 
-        let resp = self
-            .client
-            .post(format!("{}/info", Into::<String>::into(self.network)))
-            .header("Content-Type", "application/json")
-            .json(&req)
-            .send()
-            .await?;
+        self.info_request(&req).await
+    }
 
-        let status_code = resp.status().as_u16();
-        let body = resp.text().await?;
-        if status_code != 200 {
-            error!("failed to get funding history: {} - {}", status_code, body);
-            return Err(Errors::HyperLiquidApiError(status_code, body).into());
-        }
+    /// Current state of a single order, `None` when the account never placed it
+    pub async fn order_status(&self, order: OrderRef) -> Result<Option<OrderWithStatus>> {
+        debug!("fetching status of order {:?}", order);
 
-        Ok(serde_json::from_str(body.as_str())?)
+        let req = GetOrderStatus {
+            request_type: "orderStatus".into(),
+            user: self.account().to_string(),
+            oid: order,
+        };
+
+        match self.info_request(&req).await? {
+            OrderStatusLookup::Order { order } => Ok(Some(*order)),
+            OrderStatusLookup::UnknownOid => Ok(None),
+        }
     }
 
     pub async fn get_user_fills(&self, aggregate_by_time: bool) -> Result<UserFillsResponse> {
-        debug!("fetching fills for user {}", self.user);
+        debug!("fetching fills for user {}", self.account());
 
         let req = GetUserFills {
             request_type: "userFills".into(),
-            user: self.user.to_string(),
+            user: self.account().to_string(),
             aggregate_by_time,
         };
 
-        let resp = self
-            .client
-            .post(format!("{}/info", Into::<String>::into(self.network)))
-            .header("Content-Type", "application/json")
-            .json(&req)
-            .send()
-            .await?;
+        self.info_request(&req).await
+    }
 
-        let status_code = resp.status().as_u16();
-        let body = resp.text().await?;
-        if status_code != 200 {
-            error!("failed to get funding history: {} - {}", status_code, body);
-            return Err(Errors::HyperLiquidApiError(status_code, body).into());
-        }
+    /// Every fill of the account from `start` up to `end` (unix ms), paging past the 2000
+    /// fills a single response is capped at
+    pub fn user_fills_by_time(
+        &self,
+        start: u64,
+        end: Option<u64>,
+        aggregate_by_time: bool,
+    ) -> impl Stream<Item = Result<Fills>> + Send + '_ {
+        debug!(
+            "streaming fills for user {} since {}",
+            self.account(),
+            start
+        );
 
-        Ok(serde_json::from_str(body.as_str())?)
+        paginate_by_time(
+            start,
+            end,
+            FILLS_PAGE_CAP,
+            |fill: &Fills| fill.time as u64,
+            |fill: &Fills| fill.tid,
+            move |start_time| {
+                let req = GetUserFillsByTime {
+                    request_type: "userFillsByTime".into(),
+                    user: self.account().to_string(),
+                    start_time,
+                    end_time: end,
+                    aggregate_by_time,
+                };
+                async move { self.info_request(&req).await }
+            },
+        )
+    }
+
+    /// Every funding payment of the account from `start` up to `end` (unix ms)
+    pub fn user_funding_by_time(
+        &self,
+        start: u64,
+        end: Option<u64>,
+    ) -> impl Stream<Item = Result<UserTransaction>> + Send + '_ {
+        debug!(
+            "streaming funding history for user {} since {}",
+            self.account(),
+            start
+        );
+
+        paginate_by_time(
+            start,
+            end,
+            HISTORY_PAGE_CAP,
+            |funding: &UserTransaction| funding.time as u64,
+            // funding payments carry a zero hash, there is one per coin and funding time
+            |funding: &UserTransaction| (funding.hash.clone(), funding.delta.coin.clone()),
+            move |start_time| {
+                let req = GetUserHistoryByTime {
+                    request_type: "userFunding".into(),
+                    user: self.account().to_string(),
+                    start_time,
+                    end_time: end,
+                };
+                async move { self.info_request(&req).await }
+            },
+        )
+    }
+
+    /// Deposits, withdrawals, transfers and every other non funding balance change of the
+    /// account from `start` up to `end` (unix ms)
+    pub fn user_non_funding_ledger_updates(
+        &self,
+        start: u64,
+        end: Option<u64>,
+    ) -> impl Stream<Item = Result<LedgerUpdate>> + Send + '_ {
+        debug!(
+            "streaming ledger updates for user {} since {}",
+            self.account(),
+            start
+        );
+
+        paginate_by_time(
+            start,
+            end,
+            HISTORY_PAGE_CAP,
+            |update: &LedgerUpdate| update.time as u64,
+            LedgerUpdate::key,
+            move |start_time| {
+                let req = GetUserHistoryByTime {
+                    request_type: "userNonFundingLedgerUpdates".into(),
+                    user: self.account().to_string(),
+                    start_time,
+                    end_time: end,
+                };
+                async move { self.info_request(&req).await }
+            },
+        )
+    }
+
+    pub async fn get_user_twap_slice_fills(&self) -> Result<UserTwapSliceFillsResponse> {
+        debug!("fetching twap slice fills for user {}", self.account());
+
+        let req = GetUserTwapSliceFills {
+            request_type: "userTwapSliceFills".into(),
+            user: self.account().to_string(),
+        };
+
+        self.info_request(&req).await
+    }
+
+    pub async fn get_sub_accounts(&self) -> Result<Vec<SubAccount>> {
+        debug!("fetching sub-accounts for user {}", self.user);
+
+        let req = GetSubAccounts {
+            request_type: "subAccounts".into(),
+            user: self.user.to_string(),
+        };
+
+        // accounts without sub-accounts get `null`
+        let sub_accounts: Option<Vec<SubAccount>> = self.info_request(&req).await?;
+        Ok(sub_accounts.unwrap_or_default())
+    }
+
+    /// `user` adds that follower's state to the response
+    pub async fn get_vault_details(
+        &self,
+        vault: Address,
+        user: Option<Address>,
+    ) -> Result<VaultDetails> {
+        debug!("fetching details for vault {}", vault);
+
+        let req = GetVaultDetails {
+            request_type: "vaultDetails".into(),
+            vault_address: vault.to_string(),
+            user: user.map(|user| user.to_string()),
+        };
+
+        self.info_request(&req).await
+    }
+
+    pub async fn get_user_vault_equities(&self) -> Result<UserVaultEquitiesResponse> {
+        debug!("fetching vault equities for user {}", self.user);
+
+        let req = GetUserVaultEquities {
+            request_type: "userVaultEquities".into(),
+            user: self.user.to_string(),
+        };
+
+        self.info_request(&req).await
+    }
+
+    pub async fn get_l2_book(
+        &self,
+        coin: String,
+        n_sig_figs: Option<u32>,
+        mantissa: Option<u32>,
+    ) -> Result<L2Book> {
+        debug!("fetching l2 book for {}", coin);
+
+        let req = GetL2BookReq {
+            request_type: "l2Book".into(),
+            coin,
+            n_sig_figs,
+            mantissa,
+        };
+
+        self.info_request(&req).await
+    }
+
+    /// Candles of `coin` between `start` and `end` (unix ms), only the most recent 5000
+    /// candles are available
+    pub async fn candle_snapshot(
+        &self,
+        coin: String,
+        interval: Interval,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<Candle>> {
+        debug!(
+            "fetching {} candles for {} from {} to {}",
+            interval, coin, start, end
+        );
+
+        let req = GetCandleSnapshotReq {
+            request_type: "candleSnapshot".into(),
+            req: CandleSnapshotParams {
+                coin,
+                interval,
+                start_time: start,
+                end_time: end,
+            },
+        };
+
+        self.info_request(&req).await
+    }
+
+    /// Historical funding rates of `coin` since `start` (unix ms), up to `end` or now
+    pub async fn funding_history(
+        &self,
+        coin: String,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Vec<FundingRate>> {
+        debug!("fetching funding history for {} since {}", coin, start);
+
+        let req = GetFundingHistoryReq {
+            request_type: "fundingHistory".into(),
+            coin,
+            start_time: start,
+            end_time: end,
+        };
+
+        self.info_request(&req).await
+    }
+
+    /// Next funding rate of every perp across Hyperliquid and the major CEX venues
+    pub async fn predicted_fundings(&self) -> Result<PredictedFundings> {
+        debug!("fetching predicted fundings");
+
+        let req = GetInfoReq {
+            asset_type: "predictedFundings".into(),
+            dex: None,
+        };
+
+        self.info_request(&req).await
     }
 
     pub async fn get_perp_info(&self, dex: Option<String>) -> Result<PerpetualsInfo> {
@@ -186,23 +473,7 @@ impl HyperliquidClient {
             dex,
         };
 
-        let resp = self
-            .client
-            .post(format!("{}/info", Into::<String>::into(self.network)))
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await?;
-
-        let status_code = resp.status().as_u16();
-        let body = resp.text().await?;
-        if status_code != 200 {
-            error!("failed to get perp info: {} - {}", status_code, body);
-            return Err(Errors::HyperLiquidApiError(status_code, body).into());
-        }
-
-        let out: PerpetualsInfo = serde_json::from_str(body.as_str())?;
-        Ok(out)
+        self.info_request(&payload).await
     }
 
     pub async fn get_spot_info(&self, dex: Option<String>) -> Result<SpotResponse> {
@@ -213,91 +484,42 @@ impl HyperliquidClient {
             dex,
         };
 
-        let resp = self
-            .client
-            .post(format!("{}/info", Into::<String>::into(self.network)))
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await?;
-
-        let status_code = resp.status().as_u16();
-        let body = resp.text().await?;
-        if status_code != 200 {
-            error!("failed to get spot info: {} - {}", status_code, body);
-            return Err(Errors::HyperLiquidApiError(status_code, body).into());
-        }
-
-        let out: SpotResponse = serde_json::from_str(body.as_str())?;
-        Ok(out)
+        self.info_request(&payload).await
     }
 
     pub async fn get_user_spot_info(&self, dex: Option<String>) -> Result<UserSpotPosition> {
-        debug!("fetching user spot positions for {}", self.user);
+        debug!("fetching user spot positions for {}", self.account());
 
         let payload = GetUserInfoReq {
             request_type: "spotClearinghouseState".into(),
-            user: self.user.to_string(),
+            user: self.account().to_string(),
             dex,
         };
 
-        let resp = self
-            .client
-            .post(format!("{}/info", Into::<String>::into(self.network)))
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await?;
-
-        let status_code = resp.status().as_u16();
-        let body = resp.text().await?;
-        if status_code != 200 {
-            error!("failed to get user spot info: {} - {}", status_code, body);
-            return Err(Errors::HyperLiquidApiError(status_code, body).into());
-        }
-
-        debug!("user spot response: {}", body);
-        let out: UserSpotPosition = serde_json::from_str(body.as_str())?;
-
-        Ok(out)
+        self.info_request(&payload).await
     }
 
     pub async fn get_user_perp_info(&self, dex: Option<String>) -> Result<UserPerpPosition> {
-        debug!("fetching user perp positions for {}", self.user);
+        debug!("fetching user perp positions for {}", self.account());
 
         let payload = GetUserInfoReq {
             request_type: "clearinghouseState".into(),
-            user: self.user.to_string(),
+            user: self.account().to_string(),
             dex,
         };
 
-        let resp = self
-            .client
-            .post(format!("{}/info", Into::<String>::into(self.network)))
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await?;
-
-        let status_code = resp.status().as_u16();
-        let body = resp.text().await?;
-        if status_code != 200 {
-            error!("failed to get user perp info: {} - {}", status_code, body);
-            return Err(Errors::HyperLiquidApiError(status_code, body).into());
-        }
-
-        Ok(serde_json::from_str(body.as_str())?)
+        self.info_request(&payload).await
     }
 
     pub async fn update_dex_abstraction(&self, sig_chain_id: String, enabled: bool) -> Result<()> {
-        let nonce = self.nonce_manager.get_next_nonce();
+        let nonce = self.nonce_manager.get_next_nonce()?;
 
         let action: Actions = Actions::UserDexAbstraction(crate::UpdateDexAbstraction {
             sig_chain_id: sig_chain_id.clone(),
             chain: self.network.name(),
             user: self.user.to_string(),
             enabled,
-            nonce: nonce,
+            nonce,
         });
 
         let sig_chain_id_u64 = parse_chain_id(sig_chain_id.as_str())?;
@@ -308,18 +530,19 @@ impl HyperliquidClient {
                 hyperliquidChain: self.network.name(),
                 user: self.user,
                 enabled,
-                nonce: nonce,
+                nonce,
             },
             sig_chain_id_u64,
         );
 
         debug!("update abstraction hash: {:?}", hash);
-        let signature = self.signer.sign_order(hash).await?;
+        let signature = self.sign_user_action("userDexAbstraction", hash).await?;
 
         let payload = ExchangeRequest {
             nonce,
             signature,
             action: serde_json::to_value(action)?,
+            vault_address: None,
         };
 
         debug!(
@@ -329,7 +552,7 @@ impl HyperliquidClient {
 
         let resp = self
             .client
-            .post(format!("{}/exchange", Into::<String>::into(self.network)))
+            .post(format!("{}/exchange", self.network.api_url()))
             .json(&payload)
             .send()
             .await?;
@@ -356,50 +579,53 @@ impl HyperliquidClient {
             a, leverage, is_cross
         );
 
-        let nonce = self.nonce_manager.get_next_nonce();
-
-        let action: Actions = Actions::UpdateLeverage(crate::UpdateLeverage {
+        self.send_l1_action(Actions::UpdateLeverage(crate::UpdateLeverage {
             asset: a,
             is_cross,
             leverage,
-        });
+        }))
+        .await
+        .map(|_| ())
+    }
 
-        let is_mainnet = self.network == Network::Mainnet;
-        let (to_sign, domain) = generate_action_params(&action, is_mainnet, nonce)?;
+    /// Adds (`ntli > 0`) or removes margin of an isolated position, `ntli` is in micro USDC
+    /// (1_000_000 = $1) and `is_buy` is the side of the position
+    pub async fn update_isolated_margin(
+        &self,
+        a: u32,
+        is_buy: bool,
+        ntli: i64,
+    ) -> Result<ExchangeOrderResponse> {
+        info!(
+            "updating isolated margin for asset {} by {} micro USDC",
+            a, ntli
+        );
 
-        let hash = to_sign.hyperliquid_signing_hash(&domain);
+        self.send_l1_action(Actions::UpdateIsolatedMargin(UpdateIsolatedMargin {
+            asset: a,
+            is_buy,
+            ntli,
+        }))
+        .await
+    }
 
-        let signature = self.signer.sign_order(hash).await?;
+    /// Tops up the margin of an isolated-only position so it sits at `leverage`, a decimal
+    /// string such as `"3"` or `"2.5"` that is signed as given
+    pub async fn top_up_isolated_only_margin(
+        &self,
+        a: u32,
+        leverage: String,
+    ) -> Result<ExchangeOrderResponse> {
+        info!(
+            "topping up isolated only margin for asset {} to {}x",
+            a, leverage
+        );
 
-        let payload = ExchangeRequest {
-            action: serde_json::to_value(action)?,
-            signature,
-            nonce,
-        };
-
-        debug!(
-            "update leverage payload: {}",
-            serde_json::to_string(&payload).unwrap()
-        );
-
-        let resp = self
-            .client
-            .post(format!("{}/exchange", Into::<String>::into(self.network)))
-            .json(&payload)
-            .send()
-            .await?;
-
-        let status_code = resp.status().as_u16();
-        let body = resp.text().await?;
-        if status_code != 200 {
-            error!("failed to update leverage: {} - {}", status_code, body);
-            return Err(Errors::HyperLiquidApiError(status_code, body).into());
-        }
-
-        let out: ExchangeResponse = serde_json::from_str(body.as_str())?;
-        debug!("leverage update response: {:?}", out);
-
-        Ok(())
+        self.send_l1_action(Actions::TopUpIsolatedOnlyMargin(TopUpIsolatedOnlyMargin {
+            asset: a,
+            leverage,
+        }))
+        .await
     }
 
     pub async fn get_user_multi_sig_config(
@@ -413,25 +639,7 @@ impl HyperliquidClient {
             user: user.to_string(),
         };
 
-        let resp = self
-            .client
-            .post(format!("{}/info", Into::<String>::into(self.network)))
-            .header("Content-Type", "application/json")
-            .json(&req)
-            .send()
-            .await?;
-
-        let status_code = resp.status().as_u16();
-        let body = resp.text().await?;
-        if status_code != 200 {
-            error!(
-                "failed to get user multi sig config: {} - {}",
-                status_code, body
-            );
-            return Err(Errors::HyperLiquidApiError(status_code, body).into());
-        }
-
-        Ok(serde_json::from_str(body.as_str())?)
+        self.info_request(&req).await
     }
 
     pub async fn create_position_with_size_in_usd(
@@ -516,18 +724,88 @@ impl HyperliquidClient {
                 order_type: OrderType::Limit(crate::Limit { tif: "Ioc".into() }),
                 cloid: None,
             }],
-            grouping: "na".to_string(),
+            grouping: Grouping::Na,
+            builder: None,
+        })
+        .await
+    }
+
+    /// Reduce-only trigger order. `px` is the limit price once triggered, for market triggers
+    /// it acts as the worst acceptable fill price.
+    pub async fn place_trigger_order(
+        &self,
+        a: u32,
+        is_buy: bool,
+        px: String,
+        sz: String,
+        trigger_px: String,
+        is_market: bool,
+        tpsl: TpSl,
+    ) -> Result<ExchangeOrderResponse> {
+        debug!(
+            "placing {:?} trigger {} for asset {} at {} (limit: {}, market: {})",
+            tpsl,
+            if is_buy { "buy" } else { "sell" },
+            a,
+            trigger_px,
+            px,
+            is_market
+        );
+
+        self.create_position_raw(crate::BulkOrder {
+            orders: vec![OrderRequest {
+                asset: a,
+                is_buy,
+                limit_px: px,
+                sz,
+                reduce_only: true,
+                order_type: OrderType::Trigger(crate::Trigger {
+                    is_market,
+                    trigger_px,
+                    tpsl,
+                }),
+                cloid: None,
+            }],
+            grouping: Grouping::Na,
+            builder: None,
         })
         .await
     }
 
+    pub async fn place_stop_loss(
+        &self,
+        a: u32,
+        is_buy: bool,
+        px: String,
+        sz: String,
+        trigger_px: String,
+        is_market: bool,
+    ) -> Result<ExchangeOrderResponse> {
+        self.place_trigger_order(a, is_buy, px, sz, trigger_px, is_market, TpSl::Sl)
+            .await
+    }
+
+    pub async fn place_take_profit(
+        &self,
+        a: u32,
+        is_buy: bool,
+        px: String,
+        sz: String,
+        trigger_px: String,
+        is_market: bool,
+    ) -> Result<ExchangeOrderResponse> {
+        self.place_trigger_order(a, is_buy, px, sz, trigger_px, is_market, TpSl::Tp)
+            .await
+    }
+
     pub async fn create_position_raw(&self, orders: BulkOrder) -> Result<ExchangeOrderResponse> {
-        let nonce: u64 = self.nonce_manager.get_next_nonce();
+        let nonce: u64 = self.nonce_manager.get_next_nonce()?;
 
         let action: Actions = Actions::Order(orders);
 
-        let is_mainnet = self.network == Network::Mainnet;
-        let (to_sign, domain) = generate_action_params(&action, is_mainnet, nonce)?;
+        let is_mainnet = self.network.is_mainnet();
+        let (to_sign, domain) =
+            generate_action_params(&action, is_mainnet, nonce, self.vault_address)?;
         let hash = to_sign.hyperliquid_signing_hash(&domain);
         let signature: SignedMessage = self.signer.sign_order(hash).await?;
 
@@ -535,6 +813,7 @@ impl HyperliquidClient {
             action: serde_json::to_value(action)?,
             signature,
             nonce,
+            vault_address: self.vault_param(),
         };
 
         debug!(
@@ -542,22 +821,66 @@ impl HyperliquidClient {
             serde_json::to_string(&payload).unwrap()
         );
 
-        let resp = self
-            .client
-            .post(format!("{}/exchange", Into::<String>::into(self.network)))
-            .json(&payload)
-            .send()
-            .await?;
-
-        let status_code = resp.status().as_u16();
-        let body = resp.text().await?;
-        if status_code != 200 {
-            debug!("failed to create position: {} - {}", status_code, body);
-            return Err(Errors::HyperLiquidApiError(status_code, body).into());
+        let out = self.exchange_request(&payload).await?;
+        debug!("order response: {:?}", out);
+        if out.status != *"ok" {
+            return Err(Errors::HyperLiquidApiError(100, out.response.to_string()).into());
         }
 
-        let out: ExchangeResponse = serde_json::from_str(body.as_str())?;
-        debug!("order response: {:?}", out);
+        Ok(serde_json::from_value(out.response)?)
+    }
+
+    pub async fn modify_order(
+        &self,
+        oid: OrderRef,
+        order: OrderRequest,
+    ) -> Result<ExchangeOrderResponse> {
+        debug!("modifying order {:?} to {:?}", oid, order);
+
+        self.send_l1_action(Actions::Modify(ModifyOrder { oid, order }))
+            .await
+    }
+
+    pub async fn batch_modify(&self, modifies: Vec<ModifyOrder>) -> Result<ExchangeOrderResponse> {
+        debug!("modifying {} orders", modifies.len());
+
+        self.send_l1_action(Actions::BatchModify(BatchModify { modifies }))
+            .await
+    }
+
+    /// Signs `action` as an L1 action and submits it, decoding the exchange's statuses
+    async fn send_l1_action(&self, action: Actions) -> Result<ExchangeOrderResponse> {
+        self.send_l1_action_for(action, self.vault_address).await
+    }
+
+    /// Same as `send_l1_action` but executed for `vault`, actions managing sub-accounts and
+    /// vault deposits are always sent by the master account itself
+    async fn send_l1_action_for(
+        &self,
+        action: Actions,
+        vault: Option<Address>,
+    ) -> Result<ExchangeOrderResponse> {
+        let nonce = self.nonce_manager.get_next_nonce()?;
+
+        let is_mainnet = self.network.is_mainnet();
+        let (to_sign, domain) = generate_action_params(&action, is_mainnet, nonce, vault)?;
+        let hash = to_sign.hyperliquid_signing_hash(&domain);
+        let signature = self.signer.sign_order(hash).await?;
+
+        let payload = ExchangeRequest {
+            action: serde_json::to_value(action)?,
+            signature,
+            nonce,
+            vault_address: vault.map(|vault| vault.to_string().to_lowercase()),
+        };
+
+        debug!(
+            "l1 action payload: {}",
+            serde_json::to_string(&payload).unwrap()
+        );
+
+        let out = self.exchange_request(&payload).await?;
+        debug!("l1 action response: {:?}", out);
         if out.status != *"ok" {
             return Err(Errors::HyperLiquidApiError(100, out.response.to_string()).into());
         }
@@ -573,13 +896,13 @@ impl HyperliquidClient {
     ) -> Result<()> {
         debug!("transferring ${} USD to spot", amount);
 
-        let nonce = self.nonce_manager.get_next_nonce();
+        let nonce = self.nonce_manager.get_next_nonce()?;
 
         let transfer_req = TransferRequest {
             chain: self.network.name(),
-            sig_chain_id: sig_chain_id,
+            sig_chain_id,
             amount: amount.to_string(),
-            to_perp: to_perp,
+            to_perp,
             nonce,
         };
 
@@ -593,16 +916,17 @@ impl HyperliquidClient {
                 hyperliquidChain: transfer_req.chain.clone(),
                 amount: transfer_req.amount.clone(),
                 toPerp: transfer_req.to_perp,
-                nonce: nonce,
+                nonce,
             },
             sig_chain_id_u64,
         );
 
-        let signature = self.signer.sign_order(hash).await?;
+        let signature = self.sign_user_action("usdClassTransfer", hash).await?;
         let payload = ExchangeRequest {
             nonce,
             signature,
             action: serde_json::to_value(Actions::UsdClassTransfer(transfer_req))?,
+            vault_address: None,
         };
 
         debug!(
@@ -612,7 +936,7 @@ impl HyperliquidClient {
 
         let resp = self
             .client
-            .post(format!("{}/exchange", Into::<String>::into(self.network)))
+            .post(format!("{}/exchange", self.network.api_url()))
             .json(&payload)
             .send()
             .await?;
@@ -636,7 +960,7 @@ impl HyperliquidClient {
     pub async fn send_asset_to_dex(&self, req: SendAssetRequest) -> Result<()> {
         debug!("transferring to dex {}", req.dst_dex.clone());
         let mut transfer_req = req.clone();
-        let nonce = self.nonce_manager.get_next_nonce();
+        let nonce = self.nonce_manager.get_next_nonce()?;
         transfer_req.nonce = nonce;
 
         let sig_chain_id_u64 = parse_chain_id(&transfer_req.sig_chain_id)?;
@@ -653,18 +977,19 @@ impl HyperliquidClient {
                 token: transfer_req.token.clone(),
                 amount: transfer_req.amount.clone(),
                 fromSubAccount: transfer_req.from_sub_account.clone(),
-                nonce: nonce,
+                nonce,
             },
             sig_chain_id_u64,
         );
 
         debug!("transfer hash: {:?}", hash);
-        let signature = self.signer.sign_order(hash).await?;
+        let signature = self.sign_user_action("sendAsset", hash).await?;
 
         let payload = ExchangeRequest {
             nonce,
             signature,
             action: serde_json::to_value(Actions::SendAsset(transfer_req))?,
+            vault_address: None,
         };
 
         debug!(
@@ -674,7 +999,7 @@ impl HyperliquidClient {
 
         let resp = self
             .client
-            .post(format!("{}/exchange", Into::<String>::into(self.network)))
+            .post(format!("{}/exchange", self.network.api_url()))
             .json(&payload)
             .send()
             .await?;
@@ -707,159 +1032,568 @@ impl HyperliquidClient {
     pub async fn cancel_order_raw(&self, orders: BulkCancel) -> Result<ExchangeOrderResponse> {
         debug!("cancelling order raw {:?}", orders);
 
-        let nonce = self.nonce_manager.get_next_nonce();
-        let action: Actions = Actions::Cancel(orders);
+        let nonce = self.nonce_manager.get_next_nonce()?;
+        let action: Actions = Actions::Cancel(orders);
+
+        let is_mainnet = self.network.is_mainnet();
+        let (to_sign, domain) =
+            generate_action_params(&action, is_mainnet, nonce, self.vault_address)?;
+        let hash = to_sign.hyperliquid_signing_hash(&domain);
+        let signature = self.signer.sign_order(hash).await?;
+
+        let payload = ExchangeRequest {
+            action: serde_json::to_value(action)?,
+            signature,
+            nonce,
+            vault_address: self.vault_param(),
+        };
+
+        debug!(
+            "cancel order payload: {}",
+            serde_json::to_string(&payload).unwrap()
+        );
+
+        let out = self.exchange_request(&payload).await?;
+        debug!("cancel order response: {:?}", out);
+
+        Ok(serde_json::from_value(out.response)?)
+    }
+
+    pub async fn cancel_by_cloid(&self, a: u32, cloid: Cloid) -> Result<ExchangeOrderResponse> {
+        debug!("cancelling order {} for asset {}", cloid, a);
+        self.cancel_by_cloid_raw(BulkCancelByCloid {
+            cancels: vec![CancelByCloid { asset: a, cloid }],
+        })
+        .await
+    }
+
+    pub async fn cancel_by_cloid_raw(
+        &self,
+        orders: BulkCancelByCloid,
+    ) -> Result<ExchangeOrderResponse> {
+        debug!("cancelling orders by cloid {:?}", orders);
+        self.send_l1_action(Actions::CancelByCloid(orders)).await
+    }
+
+    /// Cancels every open order at `time` (unix ms, at least 5s ahead), `None` clears a
+    /// previously scheduled cancel
+    pub async fn schedule_cancel(&self, time: Option<u64>) -> Result<ExchangeOrderResponse> {
+        debug!("scheduling cancel of all orders at {:?}", time);
+        self.send_l1_action(Actions::ScheduleCancel(ScheduleCancel { time }))
+            .await
+    }
+
+    /// Works `sz` over `minutes` with market suborders, the twap id to cancel it with comes
+    /// back in [`TwapStatus::Running`]
+    pub async fn place_twap(
+        &self,
+        a: u32,
+        is_buy: bool,
+        sz: String,
+        minutes: u64,
+        randomize: bool,
+        reduce_only: bool,
+    ) -> Result<ExchangeOrderResponse> {
+        debug!(
+            "placing twap {} of {} for asset {} over {} minutes",
+            if is_buy { "buy" } else { "sell" },
+            sz,
+            a,
+            minutes
+        );
+
+        self.send_l1_action(Actions::TwapOrder(TwapOrder {
+            twap: TwapRequest {
+                asset: a,
+                is_buy,
+                sz,
+                reduce_only,
+                minutes,
+                randomize,
+            },
+        }))
+        .await
+    }
+
+    pub async fn cancel_twap(&self, a: u32, twap_id: u64) -> Result<ExchangeOrderResponse> {
+        debug!("cancelling twap {} for asset {}", twap_id, a);
+        self.send_l1_action(Actions::TwapCancel(TwapCancel { asset: a, twap_id }))
+            .await
+    }
+
+    /// Creates a sub-account of this account and returns its address
+    pub async fn create_sub_account(&self, name: String) -> Result<Address> {
+        info!("creating sub-account {}", name);
+
+        let out = self
+            .send_l1_action_for(Actions::CreateSubAccount(CreateSubAccount { name }), None)
+            .await?;
+
+        match out {
+            ExchangeOrderResponse::CreateSubAccount(address) => Ok(address.parse()?),
+            other => {
+                Err(Errors::DataError("sub-account address".into(), format!("{:?}", other)).into())
+            }
+        }
+    }
+
+    /// Moves perp USDC between this account and `sub`, `usd` is in micro USDC (1_000_000 = $1)
+    pub async fn sub_account_transfer(
+        &self,
+        sub: Address,
+        is_deposit: bool,
+        usd: u64,
+    ) -> Result<ExchangeOrderResponse> {
+        info!(
+            "{} {} micro USDC {} sub-account {}",
+            if is_deposit {
+                "depositing"
+            } else {
+                "withdrawing"
+            },
+            usd,
+            if is_deposit { "into" } else { "from" },
+            sub
+        );
+
+        self.send_l1_action_for(
+            Actions::SubAccountTransfer(SubAccountTransfer {
+                sub_account_user: sub.to_string().to_lowercase(),
+                is_deposit,
+                usd,
+            }),
+            None,
+        )
+        .await
+    }
+
+    /// Moves spot `token` (`name:token_id`) between this account and `sub`
+    pub async fn sub_account_spot_transfer(
+        &self,
+        sub: Address,
+        is_deposit: bool,
+        token: String,
+        amount: String,
+    ) -> Result<ExchangeOrderResponse> {
+        info!(
+            "{} {} {} {} sub-account {}",
+            if is_deposit {
+                "depositing"
+            } else {
+                "withdrawing"
+            },
+            amount,
+            token,
+            if is_deposit { "into" } else { "from" },
+            sub
+        );
+
+        self.send_l1_action_for(
+            Actions::SubAccountSpotTransfer(SubAccountSpotTransfer {
+                sub_account_user: sub.to_string().to_lowercase(),
+                is_deposit,
+                token,
+                amount,
+            }),
+            None,
+        )
+        .await
+    }
+
+    /// Deposits into or withdraws from `vault`, `usd` is in micro USDC (1_000_000 = $1)
+    pub async fn vault_transfer(
+        &self,
+        vault: Address,
+        is_deposit: bool,
+        usd: u64,
+    ) -> Result<ExchangeOrderResponse> {
+        info!(
+            "{} {} micro USDC {} vault {}",
+            if is_deposit {
+                "depositing"
+            } else {
+                "withdrawing"
+            },
+            usd,
+            if is_deposit { "into" } else { "from" },
+            vault
+        );
+
+        self.send_l1_action_for(
+            Actions::VaultTransfer(VaultTransfer {
+                vault_address: vault.to_string().to_lowercase(),
+                is_deposit,
+                usd,
+            }),
+            None,
+        )
+        .await
+    }
+
+    /// Dead man switch: every `interval` the scheduled cancel is pushed to `timeout` from now,
+    /// so open orders are cancelled once this process stops refreshing it. The last deadline
+    /// stays armed after `cancellation` fires.
+    ///
+    /// `timeout` must be at least the exchange's 5s minimum. Failed refreshes are retried until
+    /// the next one could no longer land before the deadline armed last (or when nothing was
+    /// armed yet), the handle then resolves to the error and the switch is no longer refreshed.
+    pub fn spawn_dead_man_switch(
+        self: Arc<Self>,
+        timeout: Duration,
+        interval: Duration,
+        cancellation: CancellationToken,
+    ) -> Result<JoinHandle<Result<()>>> {
+        if timeout < Duration::from_millis(MIN_SCHEDULE_CANCEL_DELAY_MS) {
+            return Err(anyhow::anyhow!(
+                "dead man switch timeout {:?} is below the exchange minimum of {}ms",
+                timeout,
+                MIN_SCHEDULE_CANCEL_DELAY_MS
+            ));
+        }
+        if interval >= timeout {
+            return Err(anyhow::anyhow!(
+                "dead man switch interval {:?} must be shorter than its timeout {:?}",
+                interval,
+                timeout
+            ));
+        }
+
+        Ok(tokio::spawn(async move {
+            info!("starting dead man switch with {:?} timeout", timeout);
+            let mut ticker = tokio::time::interval(interval);
+            let mut armed_until: Option<Instant> = None;
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {
+                        let sent = Instant::now();
+                        let deadline = SystemTime::now()
+                            .duration_since(SystemTime::UNIX_EPOCH)
+                            .unwrap_or_default()
+                            .saturating_add(timeout)
+                            .as_millis() as u64;
+
+                        match self.schedule_cancel(Some(deadline)).await {
+                            Err(e) if armed_until
+                                .is_some_and(|armed| Instant::now() + interval < armed) =>
+                            {
+                                warn!("failed to push dead man switch, retrying: {}", e);
+                            }
+                            Err(e) => {
+                                error!("dead man switch can no longer be kept armed: {}", e);
+                                return Err(e);
+                            }
+                            _ => {
+                                armed_until = Some(sent + timeout);
+                                debug!("dead man switch pushed to {}", deadline);
+                            }
+                        }
+                    }
+                    _ = cancellation.cancelled() => {
+                        info!("stopping dead man switch");
+                        return Ok(());
+                    }
+                }
+            }
+        }))
+    }
+
+    /// Deploy actions belong to the deployer account itself, they are never sent for the vault
+    /// or sub-account this client trades for
+    pub async fn perp_deploy_action(
+        &self,
+        deploy_params: PerpDeployAction,
+    ) -> Result<ExchangeOrderResponse> {
+        debug!("creating perp deploy action {:?}", deploy_params);
+
+        self.send_l1_action_for(Actions::PerpDeploy(deploy_params), None)
+            .await
+    }
+
+    pub async fn convert_to_multi_sig(
+        &self,
+        sig_chain_id: String,
+        mut signers: Vec<Address>,
+        threshold: u64,
+    ) -> Result<()> {
+        let nonce = self.nonce_manager.get_next_nonce()?;
+        signers.sort();
+
+        let sig_chain_id_u64 = parse_chain_id(&sig_chain_id)?;
+        let config_str = serde_json::to_string(&MultiSigConfig {
+            authorized_users: signers.iter().map(|s| s.to_string()).collect(),
+            threshold,
+        })?;
+
+        let convert_action: ConvertToMultiSigUserRequest = ConvertToMultiSigUserRequest {
+            sig_chain_id,
+            chain: self.network.name(),
+            signers: config_str,
+            nonce,
+        };
+
+        let hash = hyperliquid_signing_hash_with_default_domain(
+            CONVERT_TO_MULTI_SIG_USER_TYPE.to_owned(),
+            ConvertToMultiSigUser {
+                hyperliquidChain: convert_action.chain.clone(),
+                signers: convert_action.signers.clone(),
+                nonce,
+            },
+            sig_chain_id_u64,
+        );
+
+        let signature = self.sign_user_action("convertToMultiSigUser", hash).await?;
+
+        let payload = ExchangeRequest {
+            nonce,
+            signature,
+            action: serde_json::to_value(Actions::ConvertToMultiSigUser(convert_action))?,
+            vault_address: None,
+        };
+
+        let resp = self
+            .client
+            .post(format!("{}/exchange", self.network.api_url()))
+            .json(&payload)
+            .send()
+            .await?;
+
+        let status_code = resp.status().as_u16();
+        let body = resp.text().await?;
+        if status_code != 200 {
+            error!("failed to convert to multisig: {} - {}", status_code, body);
+            return Err(Errors::HyperLiquidApiError(status_code, body).into());
+        }
+
+        let out: ExchangeResponse = serde_json::from_str(body.as_str())?;
+        debug!("convert to multisig response: {:?}", out);
+        if out.status != *"ok" {
+            return Err(Errors::HyperLiquidApiError(100, out.response.to_string()).into());
+        }
+
+        Ok(())
+    }
+
+    /// Approves `agent_address` to sign L1 actions for this account. Only one unnamed agent is
+    /// kept, approving another one replaces it, named agents are replaced by name.
+    pub async fn approve_agent(
+        &self,
+        agent_address: Address,
+        name: Option<String>,
+        sig_chain_id: String,
+    ) -> Result<()> {
+        info!("approving agent {} ({:?})", agent_address, name);
+
+        let nonce = self.nonce_manager.get_next_nonce()?;
+        let sig_chain_id_u64 = parse_chain_id(&sig_chain_id)?;
+
+        let approve_req = ApproveAgentRequest {
+            sig_chain_id,
+            chain: self.network.name(),
+            agent_address: agent_address.to_string(),
+            agent_name: name,
+            nonce,
+        };
+
+        let hash = hyperliquid_signing_hash_with_default_domain(
+            APPROVE_AGENT_TYPE.to_owned(),
+            ApproveAgent {
+                hyperliquidChain: approve_req.chain.clone(),
+                agentAddress: agent_address,
+                agentName: approve_req.agent_name.clone().unwrap_or_default(),
+                nonce,
+            },
+            sig_chain_id_u64,
+        );
+
+        let signature = self.sign_user_action("approveAgent", hash).await?;
+
+        let payload = ExchangeRequest {
+            nonce,
+            signature,
+            action: serde_json::to_value(Actions::ApproveAgent(approve_req))?,
+            vault_address: None,
+        };
+
+        debug!(
+            "approve agent payload: {}",
+            serde_json::to_string(&payload).unwrap()
+        );
+
+        let out = self.exchange_request(&payload).await?;
+        debug!("approve agent response: {:?}", out);
+        if out.status != *"ok" {
+            return Err(Errors::HyperLiquidApiError(100, out.response.to_string()).into());
+        }
+
+        Ok(())
+    }
+
+    /// Allows `builder` to charge up to `max_fee_rate` (e.g. `"0.01%"`) on orders sent with its
+    /// [`BuilderInfo`]
+    pub async fn approve_builder_fee(
+        &self,
+        builder: Address,
+        max_fee_rate: String,
+        sig_chain_id: String,
+    ) -> Result<()> {
+        info!(
+            "approving builder {} for fees up to {}",
+            builder, max_fee_rate
+        );
+
+        let nonce = self.nonce_manager.get_next_nonce()?;
+        let sig_chain_id_u64 = parse_chain_id(&sig_chain_id)?;
+
+        let approve_req = ApproveBuilderFeeRequest {
+            sig_chain_id,
+            chain: self.network.name(),
+            max_fee_rate,
+            builder: builder.to_string().to_lowercase(),
+            nonce,
+        };
 
-        let is_mainnet = self.network == Network::Mainnet;
-        let (to_sign, domain) = generate_action_params(&action, is_mainnet, nonce)?;
-        let hash = to_sign.hyperliquid_signing_hash(&domain);
-        let signature = self.signer.sign_order(hash).await?;
+        let hash = hyperliquid_signing_hash_with_default_domain(
+            APPROVE_BUILDER_FEE_TYPE.to_owned(),
+            ApproveBuilderFee {
+                hyperliquidChain: approve_req.chain.clone(),
+                maxFeeRate: approve_req.max_fee_rate.clone(),
+                builder,
+                nonce,
+            },
+            sig_chain_id_u64,
+        );
+
+        let signature = self.sign_user_action("approveBuilderFee", hash).await?;
 
         let payload = ExchangeRequest {
-            action: serde_json::to_value(action)?,
-            signature,
             nonce,
+            signature,
+            action: serde_json::to_value(Actions::ApproveBuilderFee(approve_req))?,
+            vault_address: None,
         };
 
         debug!(
-            "cancel order payload: {}",
+            "approve builder fee payload: {}",
             serde_json::to_string(&payload).unwrap()
         );
 
-        let resp = self
-            .client
-            .post(format!("{}/exchange", Into::<String>::into(self.network)))
-            .json(&payload)
-            .send()
-            .await?;
-
-        let status_code = resp.status().as_u16();
-        let body = resp.text().await?;
-        if status_code != 200 {
-            error!("failed to cancel order: {} - {}", status_code, body);
-            return Err(Errors::HyperLiquidApiError(status_code, body).into());
+        let out = self.exchange_request(&payload).await?;
+        debug!("approve builder fee response: {:?}", out);
+        if out.status != *"ok" {
+            return Err(Errors::HyperLiquidApiError(100, out.response.to_string()).into());
         }
 
-        let out: ExchangeResponse = serde_json::from_str(body.as_str())?;
-        debug!("cancel order response: {:?}", out);
-
-        Ok(serde_json::from_value(out.response)?)
+        Ok(())
     }
 
-    pub async fn perp_deploy_action(
+    /// Withdraws `amount` USDC from the perp account to `destination` on Arbitrum, the bridge
+    /// takes a flat fee out of `amount`
+    pub async fn withdraw(
         &self,
-        deploy_params: PerpDeployAction,
-    ) -> Result<ExchangeOrderResponse> {
-        debug!("creating perp deploy action {:?}", deploy_params.clone());
+        destination: Address,
+        amount: String,
+        sig_chain_id: String,
+    ) -> Result<()> {
+        info!("withdrawing ${} to {}", amount, destination);
+
+        // the exchange rejects withdrawals whose time differs from the nonce
+        let nonce = self.nonce_manager.get_next_nonce()?;
+        let sig_chain_id_u64 = parse_chain_id(&sig_chain_id)?;
 
-        let nonce = self.nonce_manager.get_next_nonce();
+        let withdraw_req = WithdrawRequest {
+            sig_chain_id,
+            chain: self.network.name(),
+            destination: destination.to_string(),
+            amount,
+            time: nonce,
+        };
 
-        let action: Actions = Actions::PerpDeploy(deploy_params.clone());
+        let hash = hyperliquid_signing_hash_with_default_domain(
+            WITHDRAW_TYPE.to_owned(),
+            Withdraw {
+                hyperliquidChain: withdraw_req.chain.clone(),
+                destination: withdraw_req.destination.clone(),
+                amount: withdraw_req.amount.clone(),
+                time: nonce,
+            },
+            sig_chain_id_u64,
+        );
 
-        let is_mainnet = self.network == Network::Mainnet;
-        let (to_sign, domain) = generate_action_params(&action, is_mainnet, nonce)?;
-        let hash = to_sign.hyperliquid_signing_hash(&domain);
-        let signature = self.signer.sign_order(hash).await?;
+        let signature = self.sign_user_action("withdraw3", hash).await?;
 
         let payload = ExchangeRequest {
-            action: serde_json::to_value(action)?,
-            signature,
             nonce,
+            signature,
+            action: serde_json::to_value(Actions::Withdraw3(withdraw_req))?,
+            vault_address: None,
         };
 
         debug!(
-            "perp deploy action: {}",
+            "withdraw payload: {}",
             serde_json::to_string(&payload).unwrap()
         );
 
-        let resp = self
-            .client
-            .post(format!("{}/exchange", Into::<String>::into(self.network)))
-            .json(&payload)
-            .send()
-            .await?;
-
-        let status_code = resp.status().as_u16();
-        let body = resp.text().await?;
-        if status_code != 200 {
-            error!(
-                "failed to call perp deploy action: {} - {}",
-                status_code, body
-            );
-            return Err(Errors::HyperLiquidApiError(status_code, body).into());
-        }
-
-        let out: ExchangeResponse = serde_json::from_str(body.as_str())?;
-        debug!("perp deploy action response: {:?}", out);
-
-        if out.response.is_string() {
-            return Ok(ExchangeOrderResponse::String(
-                out.response
-                    .as_str()
-                    .unwrap_or("empty response data".into())
-                    .into(),
-            ));
+        let out = self.exchange_request(&payload).await?;
+        debug!("withdraw response: {:?}", out);
+        if out.status != *"ok" {
+            return Err(Errors::HyperLiquidApiError(100, out.response.to_string()).into());
         }
 
-        Ok(serde_json::from_value(out.response)?)
+        Ok(())
     }
 
-    pub async fn convert_to_multi_sig(
+    /// Sends `amount` of spot `token` (`name:token_id`, see [`Token::token_string`]) to
+    /// `destination`
+    pub async fn spot_send(
         &self,
+        destination: Address,
+        token: String,
+        amount: String,
         sig_chain_id: String,
-        mut signers: Vec<Address>,
-        threshold: u64,
     ) -> Result<()> {
-        let nonce = self.nonce_manager.get_next_nonce();
-        signers.sort();
+        info!("sending {} {} to {}", amount, token, destination);
 
+        let nonce = self.nonce_manager.get_next_nonce()?;
         let sig_chain_id_u64 = parse_chain_id(&sig_chain_id)?;
-        let config_str = serde_json::to_string(&MultiSigConfig {
-            authorized_users: signers.iter().map(|s| s.to_string()).collect(),
-            threshold: threshold,
-        })?;
 
-        let convert_action: ConvertToMultiSigUserRequest = ConvertToMultiSigUserRequest {
+        let spot_send_req = SpotSendRequest {
             sig_chain_id,
             chain: self.network.name(),
-            signers: config_str,
-            nonce,
+            destination: destination.to_string(),
+            token,
+            amount,
+            time: nonce,
         };
 
         let hash = hyperliquid_signing_hash_with_default_domain(
-            CONVERT_TO_MULTI_SIG_USER_TYPE.to_owned(),
-            ConvertToMultiSigUser {
-                hyperliquidChain: convert_action.chain.clone(),
-                signers: convert_action.signers.clone(),
-                nonce,
+            SPOT_SEND_TYPE.to_owned(),
+            SpotSend {
+                hyperliquidChain: spot_send_req.chain.clone(),
+                destination: spot_send_req.destination.clone(),
+                token: spot_send_req.token.clone(),
+                amount: spot_send_req.amount.clone(),
+                time: nonce,
             },
             sig_chain_id_u64,
         );
 
-        let signature = self.signer.sign_order(hash).await?;
+        let signature = self.sign_user_action("spotSend", hash).await?;
 
         let payload = ExchangeRequest {
             nonce,
             signature,
-            action: serde_json::to_value(Actions::ConvertToMultiSigUser(convert_action))?,
+            action: serde_json::to_value(Actions::SpotSend(spot_send_req))?,
+            vault_address: None,
         };
 
-        let resp = self
-            .client
-            .post(format!("{}/exchange", Into::<String>::into(self.network)))
-            .json(&payload)
-            .send()
-            .await?;
-
-        let status_code = resp.status().as_u16();
-        let body = resp.text().await?;
-        if status_code != 200 {
-            error!("failed to convert to multisig: {} - {}", status_code, body);
-            return Err(Errors::HyperLiquidApiError(status_code, body).into());
-        }
+        debug!(
+            "spot send payload: {}",
+            serde_json::to_string(&payload).unwrap()
+        );
 
-        let out: ExchangeResponse = serde_json::from_str(body.as_str())?;
-        debug!("convert to multisig response: {:?}", out);
+        let out = self.exchange_request(&payload).await?;
+        debug!("spot send response: {:?}", out);
         if out.status != *"ok" {
             return Err(Errors::HyperLiquidApiError(100, out.response.to_string()).into());
         }
@@ -887,7 +1621,7 @@ impl HyperliquidClient {
         );
 
         // Collect signatures from leader and other signers
-        let leader_signature = self.signer.sign_order(inner_hash).await?;
+        let leader_signature = self.sign_user_action("multiSig", inner_hash).await?;
         let mut signatures: Vec<SignedMessageHex> = vec![leader_signature.into()];
 
         for other_signer in other_signers {
@@ -907,7 +1641,7 @@ impl HyperliquidClient {
         };
 
         // Generate outer signature hash and sign
-        let outer_hash = generate_multi_sig_hash(multi_sig_payload.clone(), self.network, nonce)?;
+        let outer_hash = generate_multi_sig_hash(multi_sig_payload.clone(), &self.network, nonce)?;
         let leader_outer_signature = self.signer.sign_order(outer_hash).await?;
 
         // Send the request
@@ -915,6 +1649,7 @@ impl HyperliquidClient {
             nonce,
             signature: leader_outer_signature,
             action: serde_json::to_value(Actions::MultiSig(multi_sig_payload))?,
+            vault_address: None,
         };
 
         self.send_exchange_request(payload).await
@@ -936,7 +1671,7 @@ impl HyperliquidClient {
             multi_sig_user
         );
 
-        let nonce = self.nonce_manager.get_next_nonce();
+        let nonce = self.nonce_manager.get_next_nonce()?;
 
         let transfer_req = TransferRequest {
             chain: self.network.name(),
@@ -985,7 +1720,7 @@ impl HyperliquidClient {
             amount, token, source_dex, destination_dex, destination
         );
 
-        let nonce = self.nonce_manager.get_next_nonce();
+        let nonce = self.nonce_manager.get_next_nonce()?;
 
         let send_asset_req = SendAssetRequest {
             chain: self.network.name(),
@@ -1038,7 +1773,7 @@ impl HyperliquidClient {
             amount, destination, multi_sig_user
         );
 
-        let nonce = self.nonce_manager.get_next_nonce();
+        let nonce = self.nonce_manager.get_next_nonce()?;
 
         let usd_send_req = UsdSendRequest {
             chain: self.network.name(),
@@ -1069,6 +1804,99 @@ impl HyperliquidClient {
         .await
     }
 
+    /// Multi-sig withdrawal to Arbitrum
+    pub async fn multi_sig_withdraw(
+        &self,
+        destination: Address,
+        amount: String,
+        sig_chain_id: String,
+        other_signers: Vec<Box<dyn Signer + Send + Sync>>,
+        multi_sig_user: Address,
+    ) -> Result<()> {
+        debug!(
+            "multi-sig withdraw: ${} to {} for user {}",
+            amount, destination, multi_sig_user
+        );
+
+        let nonce = self.nonce_manager.get_next_nonce()?;
+
+        let withdraw_req = WithdrawRequest {
+            chain: self.network.name(),
+            sig_chain_id: sig_chain_id.clone(),
+            destination: destination.to_string(),
+            amount: amount.clone(),
+            time: nonce,
+        };
+
+        let multisig_withdraw_data = MultiSigWithdraw {
+            hyperliquidChain: withdraw_req.chain.clone(),
+            payloadMultiSigUser: multi_sig_user,
+            outerSigner: self.user,
+            destination: withdraw_req.destination.clone(),
+            amount: withdraw_req.amount.clone(),
+            time: nonce,
+        };
+
+        self.execute_chain_multi_sig_action(
+            nonce,
+            Actions::Withdraw3(withdraw_req),
+            multisig_withdraw_data,
+            WITHDRAW_MULTISIG_TYPE.to_owned(),
+            sig_chain_id,
+            other_signers,
+            multi_sig_user,
+        )
+        .await
+    }
+
+    /// Multi-sig spot token send
+    pub async fn multi_sig_spot_send(
+        &self,
+        destination: Address,
+        token: String,
+        amount: String,
+        sig_chain_id: String,
+        other_signers: Vec<Box<dyn Signer + Send + Sync>>,
+        multi_sig_user: Address,
+    ) -> Result<()> {
+        debug!(
+            "multi-sig spot send: {} {} to {} for user {}",
+            amount, token, destination, multi_sig_user
+        );
+
+        let nonce = self.nonce_manager.get_next_nonce()?;
+
+        let spot_send_req = SpotSendRequest {
+            chain: self.network.name(),
+            sig_chain_id: sig_chain_id.clone(),
+            destination: destination.to_string(),
+            token,
+            amount,
+            time: nonce,
+        };
+
+        let multisig_spot_send_data = MultiSigSpotSend {
+            hyperliquidChain: spot_send_req.chain.clone(),
+            payloadMultiSigUser: multi_sig_user,
+            outerSigner: self.user,
+            destination: spot_send_req.destination.clone(),
+            token: spot_send_req.token.clone(),
+            amount: spot_send_req.amount.clone(),
+            time: nonce,
+        };
+
+        self.execute_chain_multi_sig_action(
+            nonce,
+            Actions::SpotSend(spot_send_req),
+            multisig_spot_send_data,
+            SPOT_SEND_MULTISIG_TYPE.to_owned(),
+            sig_chain_id,
+            other_signers,
+            multi_sig_user,
+        )
+        .await
+    }
+
     /// Multi-sig convert to multi-sig user
     pub async fn multi_sig_convert_to_multisig_user(
         &self,
@@ -1083,7 +1911,7 @@ impl HyperliquidClient {
             signers, threshold
         );
 
-        let nonce = self.nonce_manager.get_next_nonce();
+        let nonce = self.nonce_manager.get_next_nonce()?;
         let config_str = match signers {
             Some(signers) => {
                 let mut sorted_signers = signers;
@@ -1130,22 +1958,7 @@ impl HyperliquidClient {
             serde_json::to_string(&payload).unwrap()
         );
 
-        let resp = self
-            .client
-            .post(format!("{}/exchange", Into::<String>::into(self.network)))
-            .json(&payload)
-            .send()
-            .await?;
-
-        let status_code = resp.status().as_u16();
-        let body = resp.text().await?;
-
-        if status_code != 200 {
-            error!("exchange request failed: {} - {}", status_code, body);
-            return Err(Errors::HyperLiquidApiError(status_code, body).into());
-        }
-
-        let out: ExchangeResponse = serde_json::from_str(body.as_str())?;
+        let out = self.exchange_request(&payload).await?;
         debug!("exchange response: {:?}", out);
 
         if out.status != "ok" {
@@ -1164,9 +1977,9 @@ impl HyperliquidClient {
     ) -> Result<()> {
         debug!("sending multi sig l1 action {:?}", action.clone());
 
-        let nonce = self.nonce_manager.get_next_nonce();
+        let nonce = self.nonce_manager.get_next_nonce()?;
 
-        let is_mainnet = self.network == Network::Mainnet;
+        let is_mainnet = self.network.is_mainnet();
         let hash = generate_multi_sig_l1_hash(
             &action,
             multi_sig_user.to_string(),
@@ -1193,13 +2006,14 @@ impl HyperliquidClient {
             },
         };
 
-        let sig_hash = generate_multi_sig_hash(multi_sig_payload.clone(), self.network, nonce)?;
+        let sig_hash = generate_multi_sig_hash(multi_sig_payload.clone(), &self.network, nonce)?;
         let leader_outer_signature = self.signer.sign_order(sig_hash).await?;
 
         let payload = ExchangeRequest {
             nonce,
             signature: leader_outer_signature,
             action: serde_json::to_value(Actions::MultiSig(multi_sig_payload))?,
+            vault_address: None,
         };
 
         debug!(
@@ -1210,3 +2024,307 @@ impl HyperliquidClient {
         self.send_exchange_request(payload).await
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_agent_cannot_sign_user_actions() {
+        let agent = LocalWallet::signer(
+            "0x0123456789012345678901234567890123456789012345678901234567890123".into(),
+        );
+        let master = Address::repeat_byte(0x11);
+        let client = HyperliquidClient::new_agent(Network::Testnet, Box::new(agent), master);
+
+        let err = client
+            .transfer_usd(10, true, "0x66eee".into())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Errors>(),
+            Some(Errors::AgentCannotSign(action)) if action == "usdClassTransfer"
+        ));
+    }
+
+    #[test]
+    fn test_clients_of_one_key_share_nonces() {
+        let wallet = LocalWallet::signer(
+            "0x4545454545454545454545454545454545454545454545454545454545454545".into(),
+        );
+        let master = Address::repeat_byte(0x46);
+        let perp = HyperliquidClient::new(Network::Testnet, Box::new(wallet.clone()), master);
+        let spot = HyperliquidClient::new(Network::Mainnet, Box::new(wallet.clone()), master);
+        assert!(Arc::ptr_eq(&perp.nonce_manager, &spot.nonce_manager));
+
+        // an agent signs with its own key, so it draws from that key's nonces, not the master's
+        let agent = HyperliquidClient::new_agent(Network::Testnet, Box::new(wallet), master);
+        let master = NonceManager::shared(master);
+        assert!(Arc::ptr_eq(&perp.nonce_manager, &agent.nonce_manager));
+        assert!(!Arc::ptr_eq(&perp.nonce_manager, &master));
+    }
+
+    #[test]
+    fn test_clients_share_a_persistent_manager() {
+        let wallet = LocalWallet::signer(
+            "0x4747474747474747474747474747474747474747474747474747474747474747".into(),
+        );
+        let path =
+            std::env::temp_dir().join(format!("hyperqit-client-nonce-{}", std::process::id()));
+        let persistent = NonceManager::shared_persistent(wallet.address(), &path).unwrap();
+        let client = HyperliquidClient::new(
+            Network::Testnet,
+            Box::new(wallet),
+            Address::repeat_byte(0x48),
+        );
+        assert!(Arc::ptr_eq(&persistent, &client.nonce_manager));
+
+        client.nonce_manager.get_next_nonce().unwrap();
+        assert!(
+            std::fs::read_to_string(&path)
+                .unwrap()
+                .parse::<u64>()
+                .unwrap()
+                > 0
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Client whose actions go to a local websocket that acknowledges every post and hands the
+    /// posted request back. Nothing listens on the http endpoint, every call has to use the socket.
+    async fn ws_client() -> (
+        HyperliquidClient,
+        tokio::sync::mpsc::UnboundedReceiver<serde_json::Value>,
+    ) {
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws_url = format!("ws://{}", listener.local_addr().unwrap());
+        let (posted, posts) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            while let Some(message) = ws.next().await {
+                let Some(Message::Text(text)) = message.ok() else {
+                    continue;
+                };
+                let request: serde_json::Value = serde_json::from_str(text.as_str()).unwrap();
+                let response = match request["request"]["payload"]["action"]["type"].as_str() {
+                    Some("order") => serde_json::json!({
+                        "type": "order",
+                        "data": { "statuses": [{ "resting": { "oid": 7 } }] },
+                    }),
+                    Some("cancel") => {
+                        serde_json::json!({ "type": "cancel", "data": { "statuses": ["success"] } })
+                    }
+                    _ => serde_json::json!({ "type": "default" }),
+                };
+                let frame = serde_json::json!({
+                    "channel": "post",
+                    "data": {
+                        "id": request["id"],
+                        "response": {
+                            "type": "action",
+                            "payload": { "status": "ok", "response": response },
+                        },
+                    },
+                });
+                ws.send(Message::text(frame.to_string())).await.unwrap();
+                posted.send(request).unwrap();
+            }
+        });
+
+        let network = Network::Custom {
+            api_url: "http://127.0.0.1:1".into(),
+            ws_url: ws_url.clone(),
+            chain_name: "Testnet".into(),
+            is_mainnet: false,
+        };
+        let wallet = LocalWallet::signer(
+            "0x0123456789012345678901234567890123456789012345678901234567890123".into(),
+        );
+        let user = wallet.address();
+        let ws = HyperliquidWsClient::connect_url(&ws_url, Default::default())
+            .await
+            .unwrap();
+        let client = HyperliquidClient::new(network, Box::new(wallet), user).with_ws(ws);
+        (client, posts)
+    }
+
+    /// Checks a posted request carries `expected` signed by the client's key for `vault`, the
+    /// same body the http endpoint would have received
+    fn assert_posted(
+        client: &HyperliquidClient,
+        post: serde_json::Value,
+        expected: &Actions,
+        vault: Option<Address>,
+    ) {
+        assert_eq!(post["method"], "post");
+        assert_eq!(post["request"]["type"], "action");
+        let payload: ExchangeRequest =
+            serde_json::from_value(post["request"]["payload"].clone()).unwrap();
+        assert_eq!(payload.action, serde_json::to_value(expected).unwrap());
+        assert_eq!(
+            payload.vault_address,
+            vault.map(|vault| vault.to_string().to_lowercase())
+        );
+        let signer = recover_signer(
+            expected,
+            payload.nonce,
+            &payload.signature,
+            &client.network,
+            vault,
+        )
+        .unwrap();
+        assert_eq!(signer, client.user);
+    }
+
+    fn resting_order() -> BulkOrder {
+        BulkOrder {
+            orders: vec![OrderRequest {
+                asset: 0,
+                is_buy: true,
+                limit_px: "1800".into(),
+                sz: "0.1".into(),
+                reduce_only: false,
+                order_type: OrderType::Limit(Limit { tif: "Gtc".into() }),
+                cloid: None,
+            }],
+            grouping: Grouping::Na,
+            builder: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_orders_and_cancels_post_over_ws() {
+        let (client, mut posts) = ws_client().await;
+
+        let order = resting_order();
+        let cancel = BulkCancel {
+            cancels: vec![CancelOrder { asset: 0, oid: 7 }],
+        };
+        let out = client.create_position_raw(order.clone()).await.unwrap();
+        assert!(matches!(out, ExchangeOrderResponse::Order(_)));
+        let out = client.cancel_order_raw(cancel.clone()).await.unwrap();
+        assert!(matches!(out, ExchangeOrderResponse::Cancel(_)));
+
+        for expected in [Actions::Order(order), Actions::Cancel(cancel)] {
+            assert_posted(&client, posts.recv().await.unwrap(), &expected, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_leverage_posts_over_ws() {
+        let (client, mut posts) = ws_client().await;
+        client.update_leverage(3, false, 7).await.unwrap();
+
+        let expected = Actions::UpdateLeverage(UpdateLeverage {
+            asset: 3,
+            is_cross: false,
+            leverage: 7,
+        });
+        assert_posted(&client, posts.recv().await.unwrap(), &expected, None);
+    }
+
+    #[tokio::test]
+    async fn test_vault_client_deploys_as_itself() {
+        let (client, mut posts) = ws_client().await;
+        let vault = Address::repeat_byte(0x22);
+        let client = client.for_vault_or_subaccount(vault);
+
+        let order = resting_order();
+        let deploy = PerpDeployAction::HaltTrading(HaltTrading {
+            coin: "dex:COIN".into(),
+            is_halted: true,
+        });
+        client.create_position_raw(order.clone()).await.unwrap();
+        client.perp_deploy_action(deploy.clone()).await.unwrap();
+
+        // orders trade for the vault, deploy actions stay with the signing account
+        let post = posts.recv().await.unwrap();
+        assert_posted(&client, post, &Actions::Order(order), Some(vault));
+        let post = posts.recv().await.unwrap();
+        assert_posted(&client, post, &Actions::PerpDeploy(deploy), None);
+    }
+
+    #[tokio::test]
+    async fn test_dead_man_switch_rearms_until_cancelled() {
+        use crate::testing::MockExchange;
+
+        let wallet = LocalWallet::signer(
+            "0x0123456789012345678901234567890123456789012345678901234567890123".into(),
+        );
+        let user = wallet.address();
+        let server = MockExchange::new(user).serve().await.unwrap();
+        let client = Arc::new(HyperliquidClient::new(
+            server.network(),
+            Box::new(wallet),
+            user,
+        ));
+
+        // below the exchange minimum every refresh would be rejected
+        let too_short = client.clone().spawn_dead_man_switch(
+            Duration::from_secs(4),
+            Duration::from_secs(1),
+            CancellationToken::new(),
+        );
+        assert!(too_short.is_err());
+
+        let cancellation = CancellationToken::new();
+        let handle = client
+            .spawn_dead_man_switch(
+                Duration::from_secs(6),
+                Duration::from_millis(50),
+                cancellation.clone(),
+            )
+            .unwrap();
+
+        let mut deadlines = vec![];
+        while deadlines.len() < 3 {
+            if let Some(deadline) = server.scheduled_cancel()
+                && deadlines.last() != Some(&deadline)
+            {
+                deadlines.push(deadline);
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(deadlines.windows(2).all(|pair| pair[0] < pair[1]));
+
+        cancellation.cancel();
+        handle.await.unwrap().unwrap();
+        // the last deadline stays armed once the switch stops
+        assert!(server.scheduled_cancel().unwrap() >= deadlines[2]);
+    }
+
+    #[tokio::test]
+    async fn test_dead_man_switch_surfaces_failures() {
+        use crate::testing::MockExchange;
+
+        let wallet = LocalWallet::signer(
+            "0x0123456789012345678901234567890123456789012345678901234567890123".into(),
+        );
+        let user = wallet.address();
+        // signed by a key the exchange does not know, nothing ever gets armed
+        let server = MockExchange::new(Address::repeat_byte(0x11))
+            .serve()
+            .await
+            .unwrap();
+        let client = Arc::new(HyperliquidClient::new(
+            server.network(),
+            Box::new(wallet),
+            user,
+        ));
+
+        let handle = client
+            .spawn_dead_man_switch(
+                Duration::from_secs(6),
+                Duration::from_millis(50),
+                CancellationToken::new(),
+            )
+            .unwrap();
+        let err = handle.await.unwrap().unwrap_err();
+        assert!(err.to_string().contains("does not exist"));
+        assert_eq!(server.scheduled_cancel(), None);
+    }
+}
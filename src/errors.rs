@@ -16,6 +16,18 @@ pub enum Errors {
 
     #[error("funding rate is negative {0}")]
     FundRateNegative(f64),
+
+    #[error("websocket error: {0}")]
+    WebSocketError(String),
+
+    #[error("invalid cloid {0}, expected 0x followed by 32 hex characters")]
+    InvalidCloid(String),
+
+    #[error("{0} must be signed by the master key, not an agent")]
+    AgentCannotSign(String),
+
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
 }
 
 #[derive(Error, Debug, Clone)]
@@ -1,6 +1,6 @@
 use alloy::{
     dyn_abi::Eip712Domain,
-    primitives::{FixedBytes, address, keccak256},
+    primitives::{Address, FixedBytes, address, keccak256},
     sol as alloy_sol,
     sol_types::{SolStruct, eip712_domain},
 };
@@ -25,6 +25,9 @@ pub(crate) struct ExchangeRequest {
     pub action: serde_json::Value,
     pub signature: SignedMessage,
     pub nonce: u64,
+    /// vault or sub-account the action is executed for, part of the L1 action hash
+    #[serde(rename = "vaultAddress", skip_serializing_if = "Option::is_none")]
+    pub vault_address: Option<String>,
 }
 
 sol! {
@@ -74,6 +77,51 @@ sol! {
     }
 }
 
+sol! {
+    #[multisig]
+    #[derive(Serialize)]
+    struct ApproveAgent {
+        hyperliquidChain: string,
+        agentAddress: address,
+        agentName: string,
+        nonce: uint64
+    }
+}
+
+sol! {
+    #[multisig]
+    #[derive(Serialize)]
+    struct ApproveBuilderFee {
+        hyperliquidChain: string,
+        maxFeeRate: string,
+        builder: address,
+        nonce: uint64
+    }
+}
+
+sol! {
+    #[multisig]
+    #[derive(Serialize)]
+    struct Withdraw {
+        hyperliquidChain: string,
+        destination: string,
+        amount: string,
+        time: uint64
+    }
+}
+
+sol! {
+    #[multisig]
+    #[derive(Serialize)]
+    struct SpotSend {
+        hyperliquidChain: string,
+        destination: string,
+        token: string,
+        amount: string,
+        time: uint64
+    }
+}
+
 sol! {
     #[derive(Serialize)]
     struct SendMultiSig {
@@ -144,9 +192,138 @@ pub fn hyperliquid_signing_hash_with_default_domain<S: SolStruct>(
     hyperliquid_signing_hash(type_str, data, &domain)
 }
 
+/// Builds the plain or, for co-signers of a multisig user, the `MultiSig*` variant of a
+/// user-signed struct, both share every field besides the multisig envelope
+macro_rules! user_signed_hash {
+    ($req:expr, $multi_sig:expr, $plain:ident, $plain_type:expr, $multi:ident, $multi_type:expr, { $($field:ident: $value:expr),* $(,)? }) => {{
+        let sig_chain_id = parse_chain_id(&$req.sig_chain_id)?;
+        match $multi_sig {
+            None => hyperliquid_signing_hash_with_default_domain(
+                $plain_type.to_owned(),
+                $plain {
+                    hyperliquidChain: $req.chain.clone(),
+                    $($field: $value),*
+                },
+                sig_chain_id,
+            ),
+            Some((multi_sig_user, outer_signer)) => hyperliquid_signing_hash_with_default_domain(
+                $multi_type.to_owned(),
+                $multi {
+                    hyperliquidChain: $req.chain.clone(),
+                    payloadMultiSigUser: multi_sig_user,
+                    outerSigner: outer_signer,
+                    $($field: $value),*
+                },
+                sig_chain_id,
+            ),
+        }
+    }};
+}
+
+/// `(hyperliquidChain, nonce)` of a user-signed action, `None` for L1 actions and multisig
+/// envelopes
+pub fn user_signed_action_params(action: &Actions) -> Option<(&str, u64)> {
+    match action {
+        Actions::UsdClassTransfer(req) => Some((&req.chain, req.nonce)),
+        Actions::UsdSend(req) => Some((&req.chain, req.time)),
+        Actions::SendAsset(req) => Some((&req.chain, req.nonce)),
+        Actions::ConvertToMultiSigUser(req) => Some((&req.chain, req.nonce)),
+        Actions::UserDexAbstraction(req) => Some((&req.chain, req.nonce)),
+        Actions::ApproveAgent(req) => Some((&req.chain, req.nonce)),
+        Actions::ApproveBuilderFee(req) => Some((&req.chain, req.nonce)),
+        Actions::Withdraw3(req) => Some((&req.chain, req.time)),
+        Actions::SpotSend(req) => Some((&req.chain, req.time)),
+        _ => None,
+    }
+}
+
+/// EIP-712 hash a user-signed action is signed under, `None` for L1 actions and multisig
+/// envelopes. With `multi_sig` set to `(multi_sig_user, outer_signer)` it is the hash the
+/// authorized users of a multisig account sign instead.
+pub fn user_signed_action_hash(
+    action: &Actions,
+    multi_sig: Option<(Address, Address)>,
+) -> Result<Option<FixedBytes<32>>> {
+    let hash = match action {
+        Actions::UsdClassTransfer(req) => user_signed_hash!(
+            req, multi_sig,
+            UsdClassTransfer, USD_CLASS_TRANSFER_TYPE,
+            MultiSigUsdClassTransfer, USD_CLASS_TRANSFER_MULTISIG_TYPE,
+            { amount: req.amount.clone(), toPerp: req.to_perp, nonce: req.nonce }
+        ),
+        Actions::UsdSend(req) => user_signed_hash!(
+            req, multi_sig,
+            UsdSend, USD_SEND_TYPE,
+            MultiSigUsdSend, USD_SEND_MULTISIG_TYPE,
+            { destination: req.destination.clone(), amount: req.amount.clone(), time: req.time }
+        ),
+        Actions::SendAsset(req) => user_signed_hash!(
+            req, multi_sig,
+            SendAsset, SEND_ASSET_TYPE,
+            MultiSigSendAsset, SEND_ASSET_MULTISIG_TYPE,
+            {
+                destination: req.destination.clone(),
+                sourceDex: req.source_dex.clone(),
+                destinationDex: req.dst_dex.clone(),
+                token: req.token.clone(),
+                amount: req.amount.clone(),
+                fromSubAccount: req.from_sub_account.clone(),
+                nonce: req.nonce,
+            }
+        ),
+        Actions::ConvertToMultiSigUser(req) => user_signed_hash!(
+            req, multi_sig,
+            ConvertToMultiSigUser, CONVERT_TO_MULTI_SIG_USER_TYPE,
+            MultiSigConvertToMultiSigUser, CONVERT_TO_MULTI_SIG_USER_MULTISIG_TYPE,
+            { signers: req.signers.clone(), nonce: req.nonce }
+        ),
+        Actions::UserDexAbstraction(req) => user_signed_hash!(
+            req, multi_sig,
+            UserDexAbstraction, USER_DEX_ABSTRACTION_TYPE,
+            MultiSigUserDexAbstraction, USER_DEX_ABSTRACTION_MULTISIG_TYPE,
+            { user: req.user.parse()?, enabled: req.enabled, nonce: req.nonce }
+        ),
+        Actions::ApproveAgent(req) => user_signed_hash!(
+            req, multi_sig,
+            ApproveAgent, APPROVE_AGENT_TYPE,
+            MultiSigApproveAgent, APPROVE_AGENT_MULTISIG_TYPE,
+            {
+                agentAddress: req.agent_address.parse()?,
+                agentName: req.agent_name.clone().unwrap_or_default(),
+                nonce: req.nonce,
+            }
+        ),
+        Actions::ApproveBuilderFee(req) => user_signed_hash!(
+            req, multi_sig,
+            ApproveBuilderFee, APPROVE_BUILDER_FEE_TYPE,
+            MultiSigApproveBuilderFee, APPROVE_BUILDER_FEE_MULTISIG_TYPE,
+            { maxFeeRate: req.max_fee_rate.clone(), builder: req.builder.parse()?, nonce: req.nonce }
+        ),
+        Actions::Withdraw3(req) => user_signed_hash!(
+            req, multi_sig,
+            Withdraw, WITHDRAW_TYPE,
+            MultiSigWithdraw, WITHDRAW_MULTISIG_TYPE,
+            { destination: req.destination.clone(), amount: req.amount.clone(), time: req.time }
+        ),
+        Actions::SpotSend(req) => user_signed_hash!(
+            req, multi_sig,
+            SpotSend, SPOT_SEND_TYPE,
+            MultiSigSpotSend, SPOT_SEND_MULTISIG_TYPE,
+            {
+                destination: req.destination.clone(),
+                token: req.token.clone(),
+                amount: req.amount.clone(),
+                time: req.time,
+            }
+        ),
+        _ => return Ok(None),
+    };
+    Ok(Some(hash))
+}
+
 pub fn generate_multi_sig_hash(
     payload: MultiSigRequest,
-    chain: Network,
+    chain: &Network,
     nonce: u64,
 ) -> Result<FixedBytes<32>> {
     let sig_chain_id_u64 = parse_chain_id(&payload.sig_chain_id)?;
@@ -203,14 +380,21 @@ pub fn generate_multi_sig_l1_hash(
 }
 
 pub fn generate_action_params(
-    action: &crate::Actions,
+    action: &impl Serialize,
     is_mainnet: bool,
     nonce: u64,
+    vault_address: Option<Address>,
 ) -> Result<(Agent, Eip712Domain)> {
     let mut bytes =
         rmp_serde::to_vec_named(action).map_err(|e| Errors::AgentSignature(e.to_string()))?;
     bytes.extend(nonce.to_be_bytes());
-    bytes.push(0);
+    match vault_address {
+        Some(vault) => {
+            bytes.push(1);
+            bytes.extend(vault.as_slice());
+        }
+        None => bytes.push(0),
+    }
     let out: FixedBytes<32> = keccak256(bytes.clone());
     let source = if is_mainnet { "a" } else { "b" }.to_string();
     let data = Agent {
@@ -3,3 +3,6 @@ mod nonce;
 
 pub use exchange::*;
 pub use nonce::*;
+
+#[cfg(test)]
+mod golden_vectors;
@@ -1,25 +1,234 @@
 use std::{
-    sync::atomic::{AtomicU64, Ordering},
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock, Mutex},
     time::SystemTime,
 };
 
+use alloy::primitives::Address;
+
+use crate::errors::Result;
+
+/// How far past a nonce a persistent manager reserves on disk, in ms. Nonces below the
+/// reserved mark are handed out without touching the file.
+pub const NONCE_RESERVATION_MS: u64 = 10_000;
+
+static SHARED: LazyLock<Mutex<HashMap<Address, Arc<NonceManager>>>> =
+    LazyLock::new(Default::default);
+
+#[derive(Default)]
+struct NonceState {
+    last: u64,
+    reserved: u64,
+    path: Option<PathBuf>,
+}
+
+impl NonceState {
+    /// Resumes above the mark reserved at `path`, a missing file leaves the nonces as they are
+    fn persist_to(&mut self, path: PathBuf) -> Result<()> {
+        let reserved = match fs::read_to_string(&path) {
+            Ok(raw) => raw.trim().parse()?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e.into()),
+        };
+        self.last = self.last.max(reserved);
+        self.reserved = reserved;
+        self.path = Some(path);
+        Ok(())
+    }
+}
+
+/// Hands out strictly increasing nonces for one signer: the current unix ms, or one past the
+/// last nonce when calls land in the same millisecond or the clock steps back.
+///
+/// The exchange rejects reused nonces per signer, so clients signing with the same key must
+/// share one manager. [`crate::HyperliquidClient::new`] does so through
+/// [`NonceManager::shared`], [`NonceManager::shared_persistent`] makes that manager persistent
+/// and [`crate::HyperliquidClient::with_nonce_manager`] swaps in another. A persistent manager also reserves a block of nonces on disk before handing them out, so a
+/// restarted process resumes above anything it used. It does not coordinate separate
+/// processes sharing a file.
+#[derive(Default)]
 pub struct NonceManager {
-    counter: AtomicU64,
+    state: Mutex<NonceState>,
 }
 
 impl NonceManager {
     pub fn new() -> Self {
-        NonceManager {
-            counter: AtomicU64::new(0),
-        }
+        NonceManager::default()
     }
-    pub fn get_next_nonce(&self) -> u64 {
+
+    /// In-memory manager shared by every caller in this process asking for `signer`
+    pub fn shared(signer: Address) -> Arc<Self> {
+        SHARED.lock().unwrap().entry(signer).or_default().clone()
+    }
+
+    /// The [`NonceManager::shared`] manager of `signer`, persisted at `path` from now on. It is
+    /// upgraded in place, so clients already drawing from it keep sharing it with later ones.
+    pub fn shared_persistent(signer: Address, path: impl Into<PathBuf>) -> Result<Arc<Self>> {
+        let manager = NonceManager::shared(signer);
+        manager.state.lock().unwrap().persist_to(path.into())?;
+        Ok(manager)
+    }
+
+    /// Manager that resumes above the mark reserved at `path` and reserves
+    /// [`NONCE_RESERVATION_MS`] more whenever it runs past it. A missing file starts from the
+    /// clock.
+    pub fn persistent(path: impl Into<PathBuf>) -> Result<Self> {
+        let manager = NonceManager::new();
+        manager.state.lock().unwrap().persist_to(path.into())?;
+        Ok(manager)
+    }
+
+    pub fn get_next_nonce(&self) -> Result<u64> {
         let timestamp = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
+            .duration_since(SystemTime::UNIX_EPOCH)?
             .as_millis() as u64;
 
-        let counter = self.counter.fetch_add(1, Ordering::Relaxed);
-        timestamp.saturating_add(counter % 1000)
+        let mut state = self.state.lock().unwrap();
+        let next = state
+            .last
+            .checked_add(1)
+            .ok_or_else(|| anyhow::anyhow!("nonces exhausted at {}", state.last))?;
+        let nonce = timestamp.max(next);
+        if let Some(path) = &state.path
+            && nonce > state.reserved
+        {
+            let reserved = nonce
+                .checked_add(NONCE_RESERVATION_MS)
+                .ok_or_else(|| anyhow::anyhow!("cannot reserve nonces past {}", nonce))?;
+            write_durably(path, reserved)?;
+            state.reserved = reserved;
+        }
+        state.last = nonce;
+        Ok(nonce)
+    }
+
+    /// Most recent nonce handed out, 0 before the first one
+    pub fn last_nonce(&self) -> u64 {
+        self.state.lock().unwrap().last
+    }
+}
+
+/// Replaces the mark at `path` so that neither a crash nor a power loss leaves a truncated or
+/// stale one behind: the new content is synced before the rename, the rename before returning
+fn write_durably(path: &Path, mark: u64) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(mark.to_string().as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_nonces_strictly_increase_across_threads() {
+        let manager = Arc::new(NonceManager::new());
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let manager = manager.clone();
+                std::thread::spawn(move || {
+                    (0..500)
+                        .map(|_| manager.get_next_nonce().unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut nonces = vec![];
+        for handle in handles {
+            let batch = handle.join().unwrap();
+            assert!(batch.windows(2).all(|pair| pair[0] < pair[1]));
+            nonces.extend(batch);
+        }
+        nonces.sort();
+        nonces.dedup();
+        assert_eq!(nonces.len(), 2000);
+        assert_eq!(manager.last_nonce(), *nonces.last().unwrap());
+    }
+
+    #[test]
+    fn test_shared_manager_per_signer() {
+        let (a, b) = (Address::repeat_byte(0xa1), Address::repeat_byte(0xb2));
+        assert!(Arc::ptr_eq(
+            &NonceManager::shared(a),
+            &NonceManager::shared(a)
+        ));
+        assert!(!Arc::ptr_eq(
+            &NonceManager::shared(a),
+            &NonceManager::shared(b)
+        ));
+    }
+
+    #[test]
+    fn test_persistent_nonce_survives_restart() {
+        let path = std::env::temp_dir().join(format!("hyperqit-nonce-{}", std::process::id()));
+        let ahead = u64::MAX / 2;
+        fs::write(&path, ahead.to_string()).unwrap();
+
+        // the first nonce reserves a block, the ones inside it leave the file alone
+        let manager = NonceManager::persistent(&path).unwrap();
+        assert_eq!(manager.get_next_nonce().unwrap(), ahead + 1);
+        let reserved = ahead + 1 + NONCE_RESERVATION_MS;
+        assert_eq!(fs::read_to_string(&path).unwrap(), reserved.to_string());
+        assert_eq!(manager.get_next_nonce().unwrap(), ahead + 2);
+        assert_eq!(fs::read_to_string(&path).unwrap(), reserved.to_string());
+        drop(manager);
+
+        let restarted = NonceManager::persistent(&path).unwrap();
+        assert_eq!(restarted.get_next_nonce().unwrap(), reserved + 1);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            (reserved + 1 + NONCE_RESERVATION_MS).to_string()
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_shared_manager_becomes_persistent_in_place() {
+        let signer = Address::repeat_byte(0xc3);
+        let path =
+            std::env::temp_dir().join(format!("hyperqit-nonce-shared-{}", std::process::id()));
+        let ahead = u64::MAX / 2;
+        fs::write(&path, ahead.to_string()).unwrap();
+
+        let earlier = NonceManager::shared(signer);
+        let persistent = NonceManager::shared_persistent(signer, &path).unwrap();
+        assert!(Arc::ptr_eq(&earlier, &persistent));
+        assert!(Arc::ptr_eq(&persistent, &NonceManager::shared(signer)));
+        assert_eq!(earlier.get_next_nonce().unwrap(), ahead + 1);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            (ahead + 1 + NONCE_RESERVATION_MS).to_string()
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_persistent_nonce_overflow() {
+        let path = std::env::temp_dir().join(format!("hyperqit-nonce-max-{}", std::process::id()));
+        fs::write(&path, u64::MAX.to_string()).unwrap();
+        let manager = NonceManager::persistent(&path).unwrap();
+        assert!(manager.get_next_nonce().is_err());
+
+        fs::write(&path, (u64::MAX - 1).to_string()).unwrap();
+        let manager = NonceManager::persistent(&path).unwrap();
+        assert!(manager.get_next_nonce().is_err());
+        assert_eq!(manager.last_nonce(), u64::MAX - 1);
+        fs::remove_file(&path).unwrap();
     }
 }
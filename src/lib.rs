@@ -6,18 +6,26 @@ mod market_info;
 mod order_responses;
 mod requests;
 mod signing;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 mod user_data;
 mod utils;
 mod wallet;
+mod ws;
 
 pub use client::HyperliquidClient;
 pub use errors::{CmpError, Errors, Result};
+pub use internal::{NONCE_RESERVATION_MS, NonceManager};
 pub use market_info::{
-    PerpMarketInfo, SpotMarketInfo, Token, create_unified_market_info, find_market_by_name,
+    Candle, Interval, L2Book, Level, PerpMarketInfo, SpotMarketInfo, Token,
+    create_unified_market_info, find_market_by_name, get_asset_id, get_current_price,
 };
 pub use order_responses::*;
 pub use requests::*;
-pub use signing::{SignedMessage, SignedMessageHex, Signer};
+pub use signing::{
+    SignedMessage, SignedMessageHex, Signer, recover_signer, recover_user_signer, verify_multi_sig,
+};
 pub use user_data::*;
 pub use utils::*;
 pub use wallet::{HyperLiquidSigningHash, LocalWallet};
+pub use ws::*;
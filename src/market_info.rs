@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 use tracing::debug;
 
 pub type PerpetualsInfo = (UniverseInfo, Vec<PerpetualMetadata>);
@@ -56,6 +57,14 @@ pub struct SpotInfo {
     pub tokens: Vec<Token>,
 }
 
+impl SpotInfo {
+    pub fn find_token(&self, name: &str) -> Option<&Token> {
+        self.tokens
+            .iter()
+            .find(|token| token.name.eq_ignore_ascii_case(name))
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Token {
@@ -70,6 +79,13 @@ pub struct Token {
     pub deployer_trading_fee_share: String,
 }
 
+impl Token {
+    /// `name:token_id`, the form spot transfers reference a token by
+    pub fn token_string(&self) -> String {
+        format!("{}:{}", self.name, self.token_id)
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SpotUniverse {
@@ -93,6 +109,218 @@ pub struct MarketData {
     pub day_base_vlm: String,
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Level {
+    pub px: String,
+    pub sz: String,
+    pub n: u64,
+}
+
+/// levels are `[bids, asks]`, best price first
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct L2Book {
+    pub coin: String,
+    pub time: u64,
+    pub levels: [Vec<Level>; 2],
+}
+
+impl L2Book {
+    /// side a taker walks: asks when buying, bids when selling
+    fn taker_side(&self, is_buy: bool) -> &[Level] {
+        if is_buy {
+            &self.levels[1]
+        } else {
+            &self.levels[0]
+        }
+    }
+
+    /// Average fill price for a market order of `sz`, `None` when the book is too thin to
+    /// fill it
+    pub fn vwap(&self, is_buy: bool, sz: f64) -> Option<f64> {
+        if sz <= 0.0 {
+            return None;
+        }
+
+        let mut remaining = sz;
+        let mut cost = 0.0;
+        for level in self.taker_side(is_buy) {
+            let px: f64 = level.px.parse().ok()?;
+            let level_sz: f64 = level.sz.parse().ok()?;
+            let take = remaining.min(level_sz);
+            cost += take * px;
+            remaining -= take;
+            if remaining <= 0.0 {
+                return Some(cost / sz);
+            }
+        }
+
+        None
+    }
+
+    /// Worst price reached when taking `notional` (in quote) from the book, `None` when the
+    /// book is too thin
+    pub fn impact_price(&self, is_buy: bool, notional: f64) -> Option<f64> {
+        let mut filled = 0.0;
+        for level in self.taker_side(is_buy) {
+            let px: f64 = level.px.parse().ok()?;
+            let level_sz: f64 = level.sz.parse().ok()?;
+            filled += px * level_sz;
+            if filled >= notional {
+                return Some(px);
+            }
+        }
+
+        None
+    }
+}
+
+/// `n_sig_figs` (2-5) aggregates levels, `mantissa` (1, 2 or 5) is only allowed with 5 sig figs
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetL2BookReq {
+    #[serde(rename = "type")]
+    pub request_type: String,
+    pub coin: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n_sig_figs: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mantissa: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Interval {
+    #[default]
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "3m")]
+    ThreeMinutes,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "30m")]
+    ThirtyMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "2h")]
+    TwoHours,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[serde(rename = "8h")]
+    EightHours,
+    #[serde(rename = "12h")]
+    TwelveHours,
+    #[serde(rename = "1d")]
+    OneDay,
+    #[serde(rename = "3d")]
+    ThreeDays,
+    #[serde(rename = "1w")]
+    OneWeek,
+    #[serde(rename = "1M")]
+    OneMonth,
+}
+
+impl Interval {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Interval::OneMinute => "1m",
+            Interval::ThreeMinutes => "3m",
+            Interval::FiveMinutes => "5m",
+            Interval::FifteenMinutes => "15m",
+            Interval::ThirtyMinutes => "30m",
+            Interval::OneHour => "1h",
+            Interval::TwoHours => "2h",
+            Interval::FourHours => "4h",
+            Interval::EightHours => "8h",
+            Interval::TwelveHours => "12h",
+            Interval::OneDay => "1d",
+            Interval::ThreeDays => "3d",
+            Interval::OneWeek => "1w",
+            Interval::OneMonth => "1M",
+        }
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    #[serde(rename = "t")]
+    pub open_time: u64,
+    #[serde(rename = "T")]
+    pub close_time: u64,
+    #[serde(rename = "s")]
+    pub coin: String,
+    #[serde(rename = "i")]
+    pub interval: Interval,
+    #[serde(rename = "o")]
+    pub open: String,
+    #[serde(rename = "c")]
+    pub close: String,
+    #[serde(rename = "h")]
+    pub high: String,
+    #[serde(rename = "l")]
+    pub low: String,
+    #[serde(rename = "v")]
+    pub volume: String,
+    #[serde(rename = "n")]
+    pub trades: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CandleSnapshotParams {
+    pub coin: String,
+    pub interval: Interval,
+    pub start_time: u64,
+    pub end_time: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetCandleSnapshotReq {
+    #[serde(rename = "type")]
+    pub request_type: String,
+    pub req: CandleSnapshotParams,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetFundingHistoryReq {
+    #[serde(rename = "type")]
+    pub request_type: String,
+    pub coin: String,
+    pub start_time: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<u64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundingRate {
+    pub coin: String,
+    pub funding_rate: String,
+    pub premium: String,
+    pub time: u64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PredictedFunding {
+    pub funding_rate: String,
+    pub next_funding_time: u64,
+    pub funding_interval_hours: Option<u64>,
+}
+
+/// `(coin, [(venue, prediction)])`, venues are e.g. `HlPerp`, `BinPerp`, `BybitPerp`, the
+/// prediction is `None` when a venue does not list the coin
+pub type PredictedFundings = Vec<(String, Vec<(String, Option<PredictedFunding>)>)>;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UnifiedMarketInfo {
     pub perp_markets: HashMap<String, PerpMarketInfo>,
@@ -135,6 +363,13 @@ pub struct SpotMarketInfo {
     pub is_canonical: bool,
 }
 
+impl SpotMarketInfo {
+    /// `name:token_id` of the base token, see [`Token::token_string`]
+    pub fn token_string(&self) -> String {
+        format!("{}:{}", self.name, self.token_id)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CombinedMarketInfo {
     pub base_name: String,
@@ -170,7 +405,7 @@ fn process_perp_markets(
         .0
         .universe
         .into_iter()
-        .zip(perp_info.1.into_iter())
+        .zip(perp_info.1)
         .enumerate()
     {
         let perp_market = PerpMarketInfo {
@@ -336,3 +571,58 @@ pub fn get_current_price(
         price_str.parse().ok()
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn level(px: &str, sz: &str) -> Level {
+        Level {
+            px: px.into(),
+            sz: sz.into(),
+            n: 1,
+        }
+    }
+
+    #[test]
+    fn test_predicted_fundings() {
+        let raw = r#"[["BTC",[["BinPerp",{"fundingRate":"0.0001","nextFundingTime":1733961600000}],["HlPerp",{"fundingRate":"0.0000125","nextFundingTime":1733958000000,"fundingIntervalHours":1}],["BybitPerp",null]]]]"#;
+        let predicted: PredictedFundings = serde_json::from_str(raw).unwrap();
+
+        let (coin, venues) = &predicted[0];
+        assert_eq!(coin, "BTC");
+        assert_eq!(venues[1].0, "HlPerp");
+        assert_eq!(
+            venues[1].1.as_ref().unwrap().funding_interval_hours,
+            Some(1)
+        );
+        assert!(venues[2].1.is_none());
+
+        let candle: Candle = serde_json::from_str(
+            r#"{"t":1681923600000,"T":1681924499999,"s":"BTC","i":"15m","o":"29295.0","c":"29258.0","h":"29309.0","l":"29250.0","v":"0.98639","n":189}"#,
+        )
+        .unwrap();
+        assert_eq!(candle.interval, Interval::FifteenMinutes);
+    }
+
+    #[test]
+    fn test_l2_book_vwap_and_impact() {
+        let book = L2Book {
+            coin: "HYPE".into(),
+            time: 0,
+            levels: [
+                vec![level("99", "1"), level("98", "2")],
+                vec![level("101", "1"), level("102", "3")],
+            ],
+        };
+
+        assert_eq!(book.vwap(true, 1.0), Some(101.0));
+        assert_eq!(book.vwap(true, 2.0), Some(101.5));
+        assert_eq!(book.vwap(false, 3.0), Some((99.0 + 2.0 * 98.0) / 3.0));
+        assert_eq!(book.vwap(true, 5.0), None);
+
+        assert_eq!(book.impact_price(true, 100.0), Some(101.0));
+        assert_eq!(book.impact_price(true, 200.0), Some(102.0));
+        assert_eq!(book.impact_price(false, 1000.0), None);
+    }
+}
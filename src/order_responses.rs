@@ -21,6 +21,8 @@ pub enum OrderStatus {
     Error(String),
     Filled(FilledOrder),
     Success,
+    WaitingForFill,
+    WaitingForTrigger,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -29,6 +31,26 @@ pub struct OrderStatusResponse {
     pub statuses: Vec<OrderStatus>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunningTwap {
+    pub twap_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum TwapStatus {
+    Running(RunningTwap),
+    Error(String),
+    Success,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TwapStatusResponse {
+    pub status: TwapStatus,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SetGlobalResponse {
@@ -43,6 +65,10 @@ pub enum ExchangeOrderResponse {
     Cancel(OrderStatusResponse),
     SetGlobal(SetGlobalResponse),
     String(String),
+    TwapOrder(TwapStatusResponse),
+    TwapCancel(TwapStatusResponse),
+    /// address of the newly created sub-account
+    CreateSubAccount(String),
 }
 
 #[cfg(test)]
@@ -123,6 +149,46 @@ mod test {
                   }
                }
             }"#,
+            r#"{
+               "status":"ok",
+               "response":{
+                  "type":"order",
+                  "data":{
+                     "statuses":[
+                        "waitingForTrigger"
+                     ]
+                  }
+               }
+            }"#,
+            r#"{
+               "status":"ok",
+               "response":{
+                  "type":"twapOrder",
+                  "data":{
+                     "status":{
+                        "running":{
+                           "twapId":77738308
+                        }
+                     }
+                  }
+               }
+            }"#,
+            r#"{
+               "status":"ok",
+               "response":{
+                  "type":"twapCancel",
+                  "data":{
+                     "status":"success"
+                  }
+               }
+            }"#,
+            r#"{
+               "status":"ok",
+               "response":{
+                  "type":"createSubAccount",
+                  "data":"0x035605fc2f24d65300227189025e90a0d947f16c"
+               }
+            }"#,
             r#"{"status": "ok", "response": {"type": "default"}}"#,
         ];
 
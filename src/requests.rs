@@ -1,6 +1,58 @@
+use alloy::primitives::Address;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
 use crate::SignedMessageHex;
+use crate::errors::Errors;
+
+/// Client order id, 16 bytes rendered as `0x` followed by 32 lowercase hex characters
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub struct Cloid(String);
+
+impl Cloid {
+    pub fn from_u128(value: u128) -> Self {
+        Cloid(format!("0x{:032x}", value))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Cloid {
+    type Err = Errors;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        match raw.strip_prefix("0x") {
+            Some(hex) if hex.len() == 32 && hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+                Ok(Cloid(raw.to_lowercase()))
+            }
+            _ => Err(Errors::InvalidCloid(raw.to_string())),
+        }
+    }
+}
+
+impl TryFrom<String> for Cloid {
+    type Error = Errors;
+
+    fn try_from(raw: String) -> Result<Self, Self::Error> {
+        raw.parse()
+    }
+}
+
+impl From<Cloid> for String {
+    fn from(cloid: Cloid) -> Self {
+        cloid.0
+    }
+}
+
+impl fmt::Display for Cloid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -18,7 +70,7 @@ pub struct OrderRequest {
     #[serde(rename = "t", alias = "orderType")]
     pub order_type: OrderType,
     #[serde(rename = "c", alias = "cloid", skip_serializing_if = "Option::is_none")]
-    pub cloid: Option<String>,
+    pub cloid: Option<Cloid>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -26,10 +78,27 @@ pub struct Limit {
     pub tif: String,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TpSl {
+    Tp,
+    Sl,
+}
+
+// field order is part of the action hash: isMarket, triggerPx, tpsl
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Trigger {
+    pub is_market: bool,
+    pub trigger_px: String,
+    pub tpsl: TpSl,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum OrderType {
     Limit(Limit),
+    Trigger(Trigger),
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -60,6 +129,44 @@ pub enum Actions {
     ConvertToMultiSigUser(ConvertToMultiSigUserRequest),
     MultiSig(MultiSigRequest),
     UserDexAbstraction(UpdateDexAbstraction),
+    Modify(ModifyOrder),
+    BatchModify(BatchModify),
+    CancelByCloid(BulkCancelByCloid),
+    ScheduleCancel(ScheduleCancel),
+    TwapOrder(TwapOrder),
+    TwapCancel(TwapCancel),
+    ApproveAgent(ApproveAgentRequest),
+    ApproveBuilderFee(ApproveBuilderFeeRequest),
+    Withdraw3(WithdrawRequest),
+    SpotSend(SpotSendRequest),
+    CreateSubAccount(CreateSubAccount),
+    SubAccountTransfer(SubAccountTransfer),
+    SubAccountSpotTransfer(SubAccountSpotTransfer),
+    VaultTransfer(VaultTransfer),
+    UpdateIsolatedMargin(UpdateIsolatedMargin),
+    TopUpIsolatedOnlyMargin(TopUpIsolatedOnlyMargin),
+}
+
+/// An order is addressed either by its exchange assigned oid or by the client order id it was
+/// placed with
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum OrderRef {
+    Oid(u64),
+    Cloid(Cloid),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ModifyOrder {
+    pub oid: OrderRef,
+    pub order: OrderRequest,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchModify {
+    pub modifies: Vec<ModifyOrder>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -74,11 +181,125 @@ pub struct UpdateDexAbstraction {
     pub nonce: u64,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum Grouping {
+    #[default]
+    Na,
+    /// entry order followed by tp/sl children sized to it
+    NormalTpsl,
+    /// tp/sl attached to an existing position, resized as the position changes
+    PositionTpsl,
+}
+
+/// Builder attribution, `fee` is charged on top of the exchange fee in tenths of a basis point
+/// and must not exceed what the user approved through `approveBuilderFee`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BuilderInfo {
+    #[serde(rename = "b")]
+    pub builder: String,
+    #[serde(rename = "f")]
+    pub fee: u64,
+}
+
+impl BuilderInfo {
+    pub fn new(builder: Address, fee: u64) -> Self {
+        BuilderInfo {
+            builder: builder.to_string().to_lowercase(),
+            fee,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct BulkOrder {
     pub orders: Vec<OrderRequest>,
-    pub grouping: String,
+    pub grouping: Grouping,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub builder: Option<BuilderInfo>,
+}
+
+/// Builds a [`BulkOrder`] carrying take profit and stop loss legs, either as children of an
+/// entry order (`normalTpsl`) or attached to an open position (`positionTpsl`)
+#[derive(Debug, Clone)]
+pub struct TpslOrderBuilder {
+    entry: Option<OrderRequest>,
+    asset: u32,
+    close_is_buy: bool,
+    sz: String,
+    take_profit: Option<OrderRequest>,
+    stop_loss: Option<OrderRequest>,
+}
+
+impl TpslOrderBuilder {
+    pub fn entry(order: OrderRequest) -> Self {
+        TpslOrderBuilder {
+            asset: order.asset,
+            close_is_buy: !order.is_buy,
+            sz: order.sz.clone(),
+            entry: Some(order),
+            take_profit: None,
+            stop_loss: None,
+        }
+    }
+
+    /// `is_buy` is the side that closes the position, e.g. `true` for a short
+    pub fn position(asset: u32, is_buy: bool, sz: String) -> Self {
+        TpslOrderBuilder {
+            entry: None,
+            asset,
+            close_is_buy: is_buy,
+            sz,
+            take_profit: None,
+            stop_loss: None,
+        }
+    }
+
+    pub fn take_profit(mut self, trigger_px: String, px: String, is_market: bool) -> Self {
+        self.take_profit = Some(self.leg(trigger_px, px, is_market, TpSl::Tp));
+        self
+    }
+
+    pub fn stop_loss(mut self, trigger_px: String, px: String, is_market: bool) -> Self {
+        self.stop_loss = Some(self.leg(trigger_px, px, is_market, TpSl::Sl));
+        self
+    }
+
+    fn leg(&self, trigger_px: String, px: String, is_market: bool, tpsl: TpSl) -> OrderRequest {
+        OrderRequest {
+            asset: self.asset,
+            is_buy: self.close_is_buy,
+            limit_px: px,
+            sz: self.sz.clone(),
+            reduce_only: true,
+            order_type: OrderType::Trigger(Trigger {
+                is_market,
+                trigger_px,
+                tpsl,
+            }),
+            cloid: None,
+        }
+    }
+
+    pub fn build(self) -> BulkOrder {
+        let grouping = if self.entry.is_some() {
+            Grouping::NormalTpsl
+        } else {
+            Grouping::PositionTpsl
+        };
+
+        BulkOrder {
+            orders: self
+                .entry
+                .into_iter()
+                .chain(self.take_profit)
+                .chain(self.stop_loss)
+                .collect(),
+            grouping,
+            builder: None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -96,6 +317,93 @@ pub struct CancelOrder {
     pub oid: i64,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkCancelByCloid {
+    pub cancels: Vec<CancelByCloid>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelByCloid {
+    pub asset: u32,
+    pub cloid: Cloid,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSubAccount {
+    pub name: String,
+}
+
+/// `usd` is in micro USDC, `1_000_000` moves $1
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SubAccountTransfer {
+    pub sub_account_user: String,
+    pub is_deposit: bool,
+    pub usd: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SubAccountSpotTransfer {
+    pub sub_account_user: String,
+    pub is_deposit: bool,
+    pub token: String,
+    pub amount: String,
+}
+
+/// `usd` is in micro USDC, `1_000_000` moves $1
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultTransfer {
+    pub vault_address: String,
+    pub is_deposit: bool,
+    pub usd: u64,
+}
+
+/// How far ahead of now the exchange requires a scheduled cancel to be
+pub const MIN_SCHEDULE_CANCEL_DELAY_MS: u64 = 5_000;
+
+/// `time` is the unix ms at which all open orders get cancelled, `None` clears the schedule
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleCancel {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<u64>,
+}
+
+/// Sliced into suborders every 30s over `minutes`, `randomize` jitters the slice sizes
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TwapRequest {
+    #[serde(rename = "a", alias = "asset")]
+    pub asset: u32,
+    #[serde(rename = "b", alias = "isBuy")]
+    pub is_buy: bool,
+    #[serde(rename = "s", alias = "sz")]
+    pub sz: String,
+    #[serde(rename = "r", alias = "reduceOnly")]
+    pub reduce_only: bool,
+    #[serde(rename = "m", alias = "minutes")]
+    pub minutes: u64,
+    #[serde(rename = "t", alias = "randomize")]
+    pub randomize: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TwapOrder {
+    pub twap: TwapRequest,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TwapCancel {
+    #[serde(rename = "a", alias = "asset")]
+    pub asset: u32,
+    #[serde(rename = "t", alias = "twapId")]
+    pub twap_id: u64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateLeverage {
@@ -104,6 +412,23 @@ pub struct UpdateLeverage {
     pub leverage: u32,
 }
 
+/// `ntli` is the margin delta in micro USDC, negative to remove margin
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateIsolatedMargin {
+    pub asset: u32,
+    pub is_buy: bool,
+    pub ntli: i64,
+}
+
+/// Adds margin to an isolated-only position until it sits at `leverage`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TopUpIsolatedOnlyMargin {
+    pub asset: u32,
+    pub leverage: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PerpDexSchemaInput {
@@ -248,6 +573,60 @@ pub struct UsdSendRequest {
     pub time: u64,
 }
 
+/// Unnamed agents are signed with an empty `agentName` but sent without the field
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApproveAgentRequest {
+    #[serde(rename = "signatureChainId")]
+    pub sig_chain_id: String,
+    #[serde(rename = "hyperliquidChain")]
+    pub chain: String,
+    pub agent_address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_name: Option<String>,
+    pub nonce: u64,
+}
+
+/// `max_fee_rate` is a percentage string such as `"0.01%"`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApproveBuilderFeeRequest {
+    #[serde(rename = "signatureChainId")]
+    pub sig_chain_id: String,
+    #[serde(rename = "hyperliquidChain")]
+    pub chain: String,
+    pub max_fee_rate: String,
+    pub builder: String,
+    pub nonce: u64,
+}
+
+/// Bridges USDC out to `destination` on Arbitrum, `time` doubles as the action nonce
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WithdrawRequest {
+    #[serde(rename = "signatureChainId")]
+    pub sig_chain_id: String,
+    #[serde(rename = "hyperliquidChain")]
+    pub chain: String,
+    pub destination: String,
+    pub amount: String,
+    pub time: u64,
+}
+
+/// `token` is `name:token_id`, e.g. `PURR:0xc4bf3f870c0e9465323c0b6ed28096c2`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotSendRequest {
+    #[serde(rename = "signatureChainId")]
+    pub sig_chain_id: String,
+    #[serde(rename = "hyperliquidChain")]
+    pub chain: String,
+    pub destination: String,
+    pub token: String,
+    pub amount: String,
+    pub time: u64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct MultiSigRequest {
@@ -264,3 +643,263 @@ pub struct MultiSigPayload {
     pub outer_signer: String,
     pub action: Box<Actions>,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::internal::generate_action_params;
+
+    #[test]
+    fn test_trigger_order_wire_format() {
+        let order = OrderRequest {
+            asset: 4,
+            is_buy: true,
+            limit_px: "1900".into(),
+            sz: "0.1".into(),
+            reduce_only: true,
+            order_type: OrderType::Trigger(Trigger {
+                is_market: true,
+                trigger_px: "1850".into(),
+                tpsl: TpSl::Sl,
+            }),
+            cloid: None,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&order).unwrap(),
+            r#"{"a":4,"b":true,"p":"1900","s":"0.1","r":true,"t":{"trigger":{"isMarket":true,"triggerPx":"1850","tpsl":"sl"}}}"#
+        );
+    }
+
+    #[test]
+    fn test_normal_tpsl_grouping() {
+        let entry = OrderRequest {
+            asset: 4,
+            is_buy: false,
+            limit_px: "1890".into(),
+            sz: "0.1".into(),
+            reduce_only: false,
+            order_type: OrderType::Limit(Limit { tif: "Gtc".into() }),
+            cloid: None,
+        };
+
+        let bulk = TpslOrderBuilder::entry(entry)
+            .take_profit("1700".into(), "1700".into(), false)
+            .stop_loss("2000".into(), "2100".into(), true)
+            .build();
+
+        assert_eq!(bulk.grouping, Grouping::NormalTpsl);
+        assert_eq!(bulk.orders.len(), 3);
+        assert!(bulk.orders[1..].iter().all(|o| o.is_buy && o.reduce_only));
+        assert_eq!(
+            serde_json::to_value(&bulk).unwrap()["grouping"],
+            "normalTpsl"
+        );
+
+        let position = TpslOrderBuilder::position(4, true, "0.1".into())
+            .stop_loss("2000".into(), "2100".into(), true)
+            .build();
+        assert_eq!(position.grouping, Grouping::PositionTpsl);
+        assert_eq!(position.orders.len(), 1);
+    }
+
+    #[test]
+    fn test_builder_is_part_of_action_hash() {
+        let mut bulk = TpslOrderBuilder::position(4, true, "0.1".into())
+            .stop_loss("2000".into(), "2100".into(), true)
+            .build();
+        let (unattributed, _) =
+            generate_action_params(&Actions::Order(bulk.clone()), false, 1, None).unwrap();
+
+        bulk.builder = Some(BuilderInfo::new(
+            "0x8C967E73E7B15087C42A10D344CFF4C96D877F1D"
+                .parse()
+                .unwrap(),
+            10,
+        ));
+        let action = Actions::Order(bulk);
+        assert_eq!(
+            serde_json::to_value(&action).unwrap()["builder"],
+            serde_json::json!({"b": "0x8c967e73e7b15087c42a10d344cff4c96d877f1d", "f": 10})
+        );
+
+        let (attributed, _) = generate_action_params(&action, false, 1, None).unwrap();
+        assert_ne!(attributed.connectionId, unattributed.connectionId);
+    }
+
+    #[test]
+    fn test_vault_is_part_of_action_hash() {
+        let action = Actions::CreateSubAccount(CreateSubAccount {
+            name: "funding".into(),
+        });
+        let vault: Address = "0x035605fc2f24d65300227189025e90a0d947f16c"
+            .parse()
+            .unwrap();
+
+        let (own, _) = generate_action_params(&action, true, 1, None).unwrap();
+        let (for_vault, _) = generate_action_params(&action, true, 1, Some(vault)).unwrap();
+        assert_ne!(own.connectionId, for_vault.connectionId);
+    }
+
+    #[test]
+    fn test_modify_wire_format() {
+        let action = Actions::BatchModify(BatchModify {
+            modifies: vec![ModifyOrder {
+                oid: OrderRef::Oid(77738308),
+                order: OrderRequest {
+                    asset: 0,
+                    is_buy: true,
+                    limit_px: "114000".into(),
+                    sz: "0.01".into(),
+                    reduce_only: false,
+                    order_type: OrderType::Limit(Limit { tif: "Alo".into() }),
+                    cloid: None,
+                },
+            }],
+        });
+
+        assert_eq!(
+            serde_json::to_string(&action).unwrap(),
+            r#"{"type":"batchModify","modifies":[{"oid":77738308,"order":{"a":0,"b":true,"p":"114000","s":"0.01","r":false,"t":{"limit":{"tif":"Alo"}}}}]}"#
+        );
+    }
+
+    #[test]
+    fn test_cloid_validation() {
+        let cloid: Cloid = "0x00000000000000000000000000ABCDEF".parse().unwrap();
+        assert_eq!(cloid.as_str(), "0x00000000000000000000000000abcdef");
+        assert_eq!(Cloid::from_u128(0xabcdef), cloid);
+
+        assert!("0x1234".parse::<Cloid>().is_err());
+        assert!("00000000000000000000000000abcdef".parse::<Cloid>().is_err());
+        assert!(serde_json::from_str::<Cloid>(r#""0x0000000000000000000000000000000g""#).is_err());
+
+        let action = Actions::CancelByCloid(BulkCancelByCloid {
+            cancels: vec![CancelByCloid { asset: 3, cloid }],
+        });
+        assert_eq!(
+            serde_json::to_string(&action).unwrap(),
+            r#"{"type":"cancelByCloid","cancels":[{"asset":3,"cloid":"0x00000000000000000000000000abcdef"}]}"#
+        );
+    }
+
+    #[test]
+    fn test_schedule_cancel_wire_format() {
+        let arm = Actions::ScheduleCancel(ScheduleCancel {
+            time: Some(1754000000000),
+        });
+        assert_eq!(
+            serde_json::to_string(&arm).unwrap(),
+            r#"{"type":"scheduleCancel","time":1754000000000}"#
+        );
+
+        let clear = Actions::ScheduleCancel(ScheduleCancel { time: None });
+        assert_eq!(
+            serde_json::to_string(&clear).unwrap(),
+            r#"{"type":"scheduleCancel"}"#
+        );
+    }
+
+    #[test]
+    fn test_vault_transfer_wire_format() {
+        let deposit = Actions::VaultTransfer(VaultTransfer {
+            vault_address: "0xdfc24b077bc1425ad1dea75bcb6f8158e10df303".into(),
+            is_deposit: true,
+            usd: 5_000_000,
+        });
+        assert_eq!(
+            serde_json::to_string(&deposit).unwrap(),
+            r#"{"type":"vaultTransfer","vaultAddress":"0xdfc24b077bc1425ad1dea75bcb6f8158e10df303","isDeposit":true,"usd":5000000}"#
+        );
+    }
+
+    #[test]
+    fn test_twap_wire_format() {
+        let order = Actions::TwapOrder(TwapOrder {
+            twap: TwapRequest {
+                asset: 4,
+                is_buy: true,
+                sz: "12.5".into(),
+                reduce_only: false,
+                minutes: 30,
+                randomize: true,
+            },
+        });
+        assert_eq!(
+            serde_json::to_string(&order).unwrap(),
+            r#"{"type":"twapOrder","twap":{"a":4,"b":true,"s":"12.5","r":false,"m":30,"t":true}}"#
+        );
+
+        let cancel = Actions::TwapCancel(TwapCancel {
+            asset: 4,
+            twap_id: 3156,
+        });
+        assert_eq!(
+            serde_json::to_string(&cancel).unwrap(),
+            r#"{"type":"twapCancel","a":4,"t":3156}"#
+        );
+    }
+
+    #[test]
+    fn test_withdraw_wire_format() {
+        let action = Actions::Withdraw3(WithdrawRequest {
+            sig_chain_id: "0x66eee".into(),
+            chain: "Testnet".into(),
+            destination: "0x0000000000000000000000000000000000000001".into(),
+            amount: "25.5".into(),
+            time: 1754000000000,
+        });
+        assert_eq!(
+            serde_json::to_string(&action).unwrap(),
+            r#"{"type":"withdraw3","signatureChainId":"0x66eee","hyperliquidChain":"Testnet","destination":"0x0000000000000000000000000000000000000001","amount":"25.5","time":1754000000000}"#
+        );
+        assert_eq!(
+            crate::internal::WITHDRAW_TYPE,
+            "HyperliquidTransaction:Withdraw(string hyperliquidChain,string destination,string amount,uint64 time)"
+        );
+    }
+
+    #[test]
+    fn test_spot_send_token_string() {
+        let token = crate::Token {
+            name: "PURR".into(),
+            token_id: "0xc4bf3f870c0e9465323c0b6ed28096c2".into(),
+            ..Default::default()
+        };
+
+        let action = Actions::SpotSend(SpotSendRequest {
+            sig_chain_id: "0x66eee".into(),
+            chain: "Testnet".into(),
+            destination: "0x0000000000000000000000000000000000000001".into(),
+            token: token.token_string(),
+            amount: "100".into(),
+            time: 1754000000000,
+        });
+        assert_eq!(
+            serde_json::to_string(&action).unwrap(),
+            r#"{"type":"spotSend","signatureChainId":"0x66eee","hyperliquidChain":"Testnet","destination":"0x0000000000000000000000000000000000000001","token":"PURR:0xc4bf3f870c0e9465323c0b6ed28096c2","amount":"100","time":1754000000000}"#
+        );
+    }
+
+    #[test]
+    fn test_isolated_margin_wire_format() {
+        let update = Actions::UpdateIsolatedMargin(UpdateIsolatedMargin {
+            asset: 1,
+            is_buy: false,
+            ntli: -2_500_000,
+        });
+        assert_eq!(
+            serde_json::to_string(&update).unwrap(),
+            r#"{"type":"updateIsolatedMargin","asset":1,"isBuy":false,"ntli":-2500000}"#
+        );
+
+        let top_up = Actions::TopUpIsolatedOnlyMargin(TopUpIsolatedOnlyMargin {
+            asset: 1,
+            leverage: "3".into(),
+        });
+        assert_eq!(
+            serde_json::to_string(&top_up).unwrap(),
+            r#"{"type":"topUpIsolatedOnlyMargin","asset":1,"leverage":"3"}"#
+        );
+    }
+}
@@ -1,11 +1,22 @@
-use crate::errors::Result;
-use alloy::primitives::{FixedBytes, U256};
+use crate::errors::{Errors, Result};
+use crate::internal::*;
+use crate::requests::{Actions, MultiSigRequest};
+use crate::user_data::UserMultiSigConfig;
+use crate::utils::Network;
+use crate::wallet::HyperLiquidSigningHash;
+use alloy::primitives::{Address, FixedBytes, Signature, U256};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
 #[async_trait]
 pub trait Signer {
     async fn sign_order(&self, to_sign: FixedBytes<32>) -> Result<SignedMessage>;
+
+    /// Address of the signing key when known without signing, clients signing with the same
+    /// key then share nonces
+    fn address(&self) -> Option<Address> {
+        None
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -15,6 +26,14 @@ pub struct SignedMessage {
     pub v: u64,
 }
 
+impl SignedMessage {
+    /// Address whose key produced this signature over `hash`, `v` may be 27/28 or 0/1
+    pub fn recover(&self, hash: FixedBytes<32>) -> Result<Address> {
+        let signature = Signature::new(self.r, self.s, matches!(self.v, 1 | 28));
+        Ok(signature.recover_address_from_prehash(&hash)?)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SignedMessageHex {
     pub r: String,
@@ -22,6 +41,18 @@ pub struct SignedMessageHex {
     pub v: u64,
 }
 
+impl TryFrom<&SignedMessageHex> for SignedMessage {
+    type Error = anyhow::Error;
+
+    fn try_from(signed_msg: &SignedMessageHex) -> Result<Self> {
+        Ok(SignedMessage {
+            r: signed_msg.r.parse()?,
+            s: signed_msg.s.parse()?,
+            v: signed_msg.v,
+        })
+    }
+}
+
 impl From<SignedMessage> for SignedMessageHex {
     fn from(signed_msg: SignedMessage) -> Self {
         SignedMessageHex {
@@ -31,3 +62,240 @@ impl From<SignedMessage> for SignedMessageHex {
         }
     }
 }
+
+fn invalid(reason: String) -> anyhow::Error {
+    Errors::InvalidSignature(reason).into()
+}
+
+fn action_type(action: &Actions) -> String {
+    serde_json::to_value(action)
+        .ok()
+        .and_then(|value| value["type"].as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Rejects user-signed actions meant for another chain or submitted under another nonce
+fn check_user_signed(chain: &str, action_nonce: u64, nonce: u64, network: &Network) -> Result<()> {
+    if chain != network.name() {
+        return Err(invalid(format!(
+            "signed for {}, expected {}",
+            chain,
+            network.name()
+        )));
+    }
+    if action_nonce != nonce {
+        return Err(invalid(format!(
+            "action nonce {} does not match request nonce {}",
+            action_nonce, nonce
+        )));
+    }
+    Ok(())
+}
+
+/// Address that signed the L1 `action` submitted with `nonce`, `vault` being the vault or
+/// sub-account it was executed for
+pub fn recover_signer(
+    action: &Actions,
+    nonce: u64,
+    signature: &SignedMessage,
+    network: &Network,
+    vault: Option<Address>,
+) -> Result<Address> {
+    if matches!(action, Actions::MultiSig(_)) || user_signed_action_params(action).is_some() {
+        return Err(invalid(format!(
+            "{} is a user-signed action",
+            action_type(action)
+        )));
+    }
+
+    let (agent, domain) = generate_action_params(action, network.is_mainnet(), nonce, vault)?;
+    signature.recover(agent.hyperliquid_signing_hash(&domain))
+}
+
+/// Address that signed a user-signed action, or the outer signer of a multisig envelope. Like
+/// the exchange, the action has to target `network` and carry the request `nonce`.
+pub fn recover_user_signer(
+    action: &Actions,
+    nonce: u64,
+    signature: &SignedMessage,
+    network: &Network,
+) -> Result<Address> {
+    let hash = match action {
+        Actions::MultiSig(req) => generate_multi_sig_hash(req.clone(), network, nonce)?,
+        _ => {
+            let (chain, action_nonce) = user_signed_action_params(action)
+                .ok_or_else(|| invalid(format!("{} is an L1 action", action_type(action))))?;
+            check_user_signed(chain, action_nonce, nonce, network)?;
+            user_signed_action_hash(action, None)?
+                .ok_or_else(|| invalid(format!("{} is an L1 action", action_type(action))))?
+        }
+    };
+    signature.recover(hash)
+}
+
+/// Checks the co-signatures of a multisig envelope against the multisig user's config: the
+/// outer signer and every co-signer must be authorized users, no user may co-sign twice, and
+/// at least `threshold` co-signatures must be present. The outer signer may also co-sign, its
+/// signature then counts towards the threshold. Returns the co-signers in submission order.
+pub fn verify_multi_sig(
+    req: &MultiSigRequest,
+    nonce: u64,
+    network: &Network,
+    config: &UserMultiSigConfig,
+) -> Result<Vec<Address>> {
+    let multi_sig_user: Address = req.payload.multi_sig_user.parse()?;
+    let outer_signer: Address = req.payload.outer_signer.parse()?;
+    let authorized = config
+        .authorized_users
+        .iter()
+        .map(|user| user.parse::<Address>())
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    if !authorized.contains(&outer_signer) {
+        return Err(invalid(format!(
+            "outer signer {:#x} is not an authorized user of {:#x}",
+            outer_signer, multi_sig_user
+        )));
+    }
+
+    let action = req.payload.action.as_ref();
+    let hash = match user_signed_action_params(action) {
+        Some((chain, action_nonce)) => {
+            check_user_signed(chain, action_nonce, nonce, network)?;
+            user_signed_action_hash(action, Some((multi_sig_user, outer_signer)))?
+                .ok_or_else(|| invalid(format!("{} is an L1 action", action_type(action))))?
+        }
+        None => generate_multi_sig_l1_hash(
+            action,
+            req.payload.multi_sig_user.clone(),
+            req.payload.outer_signer.clone(),
+            network.is_mainnet(),
+            nonce,
+        )?,
+    };
+
+    let mut signers: Vec<Address> = vec![];
+    for signature in &req.signatures {
+        let signer = SignedMessage::try_from(signature)?.recover(hash)?;
+        if !authorized.contains(&signer) {
+            return Err(invalid(format!(
+                "{:#x} is not an authorized user of {:#x}",
+                signer, multi_sig_user
+            )));
+        }
+        if signers.contains(&signer) {
+            return Err(invalid(format!("{:#x} signed more than once", signer)));
+        }
+        signers.push(signer);
+    }
+
+    if (signers.len() as u64) < config.threshold {
+        return Err(invalid(format!(
+            "{} of {} required signatures",
+            signers.len(),
+            config.threshold
+        )));
+    }
+    Ok(signers)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::requests::{MultiSigPayload, UpdateLeverage, WithdrawRequest};
+    use crate::wallet::LocalWallet;
+
+    fn wallet(byte: u8) -> LocalWallet {
+        LocalWallet::signer(format!("0x{}", format!("{:02x}", byte).repeat(32)))
+    }
+
+    fn withdraw(nonce: u64) -> Actions {
+        Actions::Withdraw3(WithdrawRequest {
+            sig_chain_id: "0x66eee".into(),
+            chain: "Testnet".into(),
+            destination: "0x0d1d9635d0640821d15e323ac8adadfa9c111414".into(),
+            amount: "12.5".into(),
+            time: nonce,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_recover_signer() {
+        let signer = wallet(0x11);
+        let action = Actions::UpdateLeverage(UpdateLeverage {
+            asset: 4,
+            is_cross: true,
+            leverage: 10,
+        });
+        let vault = Some(Address::repeat_byte(0x22));
+        let (agent, domain) = generate_action_params(&action, false, 7, vault).unwrap();
+        let signature = signer
+            .sign_order(agent.hyperliquid_signing_hash(&domain))
+            .await
+            .unwrap();
+
+        let recovered = recover_signer(&action, 7, &signature, &Network::Testnet, vault).unwrap();
+        assert_eq!(recovered, signer.address());
+        // any other nonce, vault or chain yields a different address
+        let recovered = recover_signer(&action, 7, &signature, &Network::Testnet, None).unwrap();
+        assert_ne!(recovered, signer.address());
+        assert!(recover_signer(&withdraw(7), 7, &signature, &Network::Testnet, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_recover_user_signer() {
+        let signer = wallet(0x11);
+        let action = withdraw(1700000000000);
+        let hash = user_signed_action_hash(&action, None).unwrap().unwrap();
+        let signature = signer.sign_order(hash).await.unwrap();
+
+        let recovered =
+            recover_user_signer(&action, 1700000000000, &signature, &Network::Testnet).unwrap();
+        assert_eq!(recovered, signer.address());
+        assert!(recover_user_signer(&action, 1, &signature, &Network::Testnet).is_err());
+        assert!(
+            recover_user_signer(&action, 1700000000000, &signature, &Network::Mainnet).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_multi_sig() {
+        let (leader, cosigner, outsider) = (wallet(0x11), wallet(0x22), wallet(0x33));
+        let multi_sig_user = Address::repeat_byte(0x44);
+        let nonce = 1700000000000;
+        let action = withdraw(nonce);
+        let hash = user_signed_action_hash(&action, Some((multi_sig_user, leader.address())))
+            .unwrap()
+            .unwrap();
+
+        let mut signatures = vec![];
+        for signer in [&leader, &cosigner, &outsider] {
+            signatures.push(SignedMessageHex::from(
+                signer.sign_order(hash).await.unwrap(),
+            ));
+        }
+        let mut req = MultiSigRequest {
+            sig_chain_id: "0x66eee".into(),
+            signatures: signatures[..2].to_vec(),
+            payload: MultiSigPayload {
+                multi_sig_user: multi_sig_user.to_string().to_lowercase(),
+                outer_signer: leader.address().to_string().to_lowercase(),
+                action: Box::new(action),
+            },
+        };
+        let config = UserMultiSigConfig {
+            authorized_users: vec![leader.address().to_string(), cosigner.address().to_string()],
+            threshold: 2,
+        };
+
+        let signers = verify_multi_sig(&req, nonce, &Network::Testnet, &config).unwrap();
+        assert_eq!(signers, vec![leader.address(), cosigner.address()]);
+
+        req.signatures = vec![signatures[0].clone()];
+        let err = verify_multi_sig(&req, nonce, &Network::Testnet, &config).unwrap_err();
+        assert!(err.to_string().contains("1 of 2 required signatures"));
+
+        req.signatures = vec![signatures[0].clone(), signatures[2].clone()];
+        let err = verify_multi_sig(&req, nonce, &Network::Testnet, &config).unwrap_err();
+        assert!(err.to_string().contains("is not an authorized user"));
+    }
+}
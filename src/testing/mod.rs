@@ -0,0 +1,523 @@
+//! In-process stand-in for the Hyperliquid HTTP API, so clients and strategies can be driven
+//! end to end in CI without reaching testnet.
+//!
+//! Actions are authenticated like the exchange does it: the EIP-712 hash is rebuilt from the
+//! submitted payload and the recovered address must be the configured signer. Orders execute
+//! against a single mark price per market, and balances, positions and resting orders of the
+//! one account live in memory. Actions outside orders, cancels, leverage, usd class transfers
+//! and scheduled cancels are rejected rather than acknowledged.
+//!
+//! The websocket endpoint answers `post` requests like `/info` and `/exchange`, it publishes
+//! no subscription feeds.
+
+mod state;
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use alloy::primitives::Address;
+use axum::{
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use futures::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+use tokio_tungstenite::{accept_async, tungstenite::Message};
+use tokio_util::sync::CancellationToken;
+
+use crate::errors::Result;
+use crate::internal::{ExchangeRequest, ExchangeResponse};
+use crate::requests::Actions;
+use crate::user_data::{Fills, OpenOrder};
+use crate::utils::Network;
+use state::{MockMarket, MockState};
+
+type SharedState = Arc<Mutex<MockState>>;
+
+/// Markets and starting balances of a [`MockServer`]
+pub struct MockExchange {
+    state: MockState,
+}
+
+impl MockExchange {
+    /// Exchange that only accepts actions signed by `signer`, the account's own key or the
+    /// agent trading for it
+    pub fn new(signer: Address) -> Self {
+        MockExchange {
+            state: MockState::new(signer),
+        }
+    }
+
+    /// Lists a perp, asset ids follow listing order. Funding starts at the 0.00125% base rate.
+    pub fn with_perp(mut self, name: &str, mark_px: f64, sz_decimals: i64) -> Self {
+        self.state.perps.push(MockMarket {
+            name: name.into(),
+            sz_decimals,
+            max_leverage: 50,
+            mark_px,
+            funding: 0.0000125,
+        });
+        self
+    }
+
+    /// Lists a spot token quoted in USDC, its asset id is 10000 + listing index
+    pub fn with_spot(mut self, name: &str, mark_px: f64, sz_decimals: i64) -> Self {
+        self.state.spots.push(MockMarket {
+            name: name.into(),
+            sz_decimals,
+            max_leverage: 1,
+            mark_px,
+            funding: 0.0,
+        });
+        self
+    }
+
+    /// Overrides the hourly funding rate of a listed perp
+    pub fn with_funding(mut self, coin: &str, rate: f64) -> Self {
+        self.perp_mut(coin).funding = rate;
+        self
+    }
+
+    /// Overrides the max leverage of a listed perp, 50 by default
+    pub fn with_max_leverage(mut self, coin: &str, max_leverage: i64) -> Self {
+        self.perp_mut(coin).max_leverage = max_leverage;
+        self
+    }
+
+    /// USDC available as perp margin
+    pub fn with_perp_balance(mut self, usdc: f64) -> Self {
+        self.state.perp_usdc = usdc;
+        self
+    }
+
+    /// Credits a spot balance, `coin` is "USDC" or a token listed with [`Self::with_spot`]
+    pub fn with_spot_balance(mut self, coin: &str, amount: f64) -> Self {
+        self.state.credit_spot(coin, amount);
+        self
+    }
+
+    fn perp_mut(&mut self, coin: &str) -> &mut MockMarket {
+        self.state
+            .perps
+            .iter_mut()
+            .find(|market| market.name == coin)
+            .unwrap_or_else(|| panic!("{} is not listed on the mock exchange", coin))
+    }
+
+    /// Binds ephemeral local ports and serves `/info`, `/exchange` and the websocket until the
+    /// returned server is dropped
+    pub async fn serve(self) -> Result<MockServer> {
+        let state = Arc::new(Mutex::new(self.state));
+        let app = Router::new()
+            .route("/info", post(info))
+            .route("/exchange", post(exchange))
+            .with_state(state.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let ws_listener = TcpListener::bind("127.0.0.1:0").await?;
+        let ws_addr = ws_listener.local_addr()?;
+        let cancellation = CancellationToken::new();
+        let shutdown = cancellation.clone();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app)
+                .with_graceful_shutdown(async move { shutdown.cancelled().await })
+                .await;
+        });
+        tokio::spawn(serve_ws(ws_listener, state.clone(), cancellation.clone()));
+
+        Ok(MockServer {
+            addr,
+            ws_addr,
+            state,
+            cancellation,
+        })
+    }
+}
+
+/// A running [`MockExchange`]
+pub struct MockServer {
+    addr: SocketAddr,
+    ws_addr: SocketAddr,
+    state: SharedState,
+    cancellation: CancellationToken,
+}
+
+impl MockServer {
+    /// Testnet flavoured network pointing at this server, to build clients with
+    pub fn network(&self) -> Network {
+        let state = self.state.lock().unwrap();
+        Network::Custom {
+            api_url: format!("http://{}", self.addr),
+            ws_url: format!("ws://{}", self.ws_addr),
+            chain_name: state.chain.name(),
+            is_mainnet: state.chain.is_mainnet(),
+        }
+    }
+
+    /// Moves a market to `mark_px`, executing resting and trigger orders it reaches
+    pub fn set_mark_px(&self, coin: &str, mark_px: f64) {
+        self.state.lock().unwrap().set_mark_px(coin, mark_px);
+    }
+
+    /// Accepted actions as submitted, oldest first
+    pub fn actions(&self) -> Vec<Value> {
+        self.state.lock().unwrap().actions.clone()
+    }
+
+    /// Fills of the account, newest first
+    pub fn fills(&self) -> Vec<Fills> {
+        self.state.lock().unwrap().fills()
+    }
+
+    pub fn open_orders(&self) -> Vec<OpenOrder> {
+        let mut state = self.state.lock().unwrap();
+        state.expire_scheduled_cancel();
+        state.open_orders()
+    }
+
+    /// Unix ms at which every resting order gets cancelled, armed through `scheduleCancel`
+    pub fn scheduled_cancel(&self) -> Option<u64> {
+        self.state.lock().unwrap().scheduled_cancel
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.cancellation.cancel();
+    }
+}
+
+fn unprocessable() -> Response {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "Failed to deserialize the JSON body into the target type",
+    )
+        .into_response()
+}
+
+/// Answers an `/exchange` body, `None` when it holds no action the API knows
+fn exchange_response(state: &SharedState, req: ExchangeRequest) -> Option<ExchangeResponse> {
+    let action = serde_json::from_value::<Actions>(req.action.clone()).ok()?;
+    let mut state = state.lock().unwrap();
+    state.expire_scheduled_cancel();
+    Some(state.execute(req, action))
+}
+
+/// Answers an `/info` body, `None` for request types the mock does not serve
+fn info_response(state: &SharedState, req: &Value) -> Option<serde_json::Result<Value>> {
+    let mut state = state.lock().unwrap();
+    state.expire_scheduled_cancel();
+    Some(match req["type"].as_str().unwrap_or_default() {
+        "metaAndAssetCtxs" => serde_json::to_value(state.perp_info()),
+        "spotMetaAndAssetCtxs" => serde_json::to_value(state.spot_info()),
+        "clearinghouseState" => serde_json::to_value(state.user_perp()),
+        "spotClearinghouseState" => serde_json::to_value(state.user_spot()),
+        "openOrders" => serde_json::to_value(state.open_orders()),
+        "userFills" => serde_json::to_value(state.fills()),
+        "allMids" => serde_json::to_value(state.all_mids()),
+        "userFunding" | "historicalOrders" => Ok(Value::Array(vec![])),
+        "l2Book" => match state.l2_book(req["coin"].as_str().unwrap_or_default()) {
+            Some(book) => serde_json::to_value(book),
+            None => Ok(Value::Null),
+        },
+        _ => return None,
+    })
+}
+
+async fn exchange(State(state): State<SharedState>, Json(req): Json<ExchangeRequest>) -> Response {
+    match exchange_response(&state, req) {
+        Some(out) => Json(out).into_response(),
+        None => unprocessable(),
+    }
+}
+
+async fn info(State(state): State<SharedState>, Json(req): Json<Value>) -> Response {
+    match info_response(&state, &req) {
+        Some(Ok(out)) => Json(out).into_response(),
+        Some(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        None => unprocessable(),
+    }
+}
+
+async fn serve_ws(listener: TcpListener, state: SharedState, cancellation: CancellationToken) {
+    loop {
+        let stream = tokio::select! {
+            _ = cancellation.cancelled() => return,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(_) => continue,
+            },
+        };
+        let state = state.clone();
+        let cancellation = cancellation.clone();
+        tokio::spawn(async move {
+            let Ok(mut ws) = accept_async(stream).await else {
+                return;
+            };
+            loop {
+                let message = tokio::select! {
+                    _ = cancellation.cancelled() => return,
+                    message = ws.next() => message,
+                };
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(_)) => continue,
+                    _ => return,
+                };
+                let Ok(request) = serde_json::from_str::<Value>(text.as_str()) else {
+                    continue;
+                };
+                let frame = ws_response(&state, &request);
+                if ws.send(Message::text(frame.to_string())).await.is_err() {
+                    return;
+                }
+            }
+        });
+    }
+}
+
+/// Frame answering a websocket request, subscriptions get an error since no feed is published
+fn ws_response(state: &SharedState, request: &Value) -> Value {
+    let method = request["method"].as_str().unwrap_or_default();
+    if method == "ping" {
+        return json!({ "channel": "pong" });
+    }
+    if method != "post" {
+        return json!({
+            "channel": "error",
+            "data": format!("{} is not supported by the mock exchange", request),
+        });
+    }
+
+    let payload = &request["request"]["payload"];
+    let response = match request["request"]["type"].as_str().unwrap_or_default() {
+        "info" => match info_response(state, payload) {
+            Some(Ok(data)) => Some(json!({
+                "type": "info",
+                "payload": { "type": payload["type"], "data": data },
+            })),
+            Some(Err(e)) => Some(json!({ "type": "error", "payload": e.to_string() })),
+            None => None,
+        },
+        "action" => serde_json::from_value(payload.clone())
+            .ok()
+            .and_then(|req| exchange_response(state, req))
+            .map(|out| json!({ "type": "action", "payload": out })),
+        _ => None,
+    };
+    let response = response.unwrap_or_else(|| {
+        json!({
+            "type": "error",
+            "payload": "Failed to deserialize the JSON body into the target type",
+        })
+    });
+    json!({ "channel": "post", "data": { "id": request["id"], "response": response } })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        BulkOrder, ExchangeOrderResponse, Grouping, HyperliquidClient, HyperliquidWsClient, Limit,
+        LocalWallet, OrderRequest, OrderStatus, OrderType, TpSl, create_unified_market_info,
+    };
+
+    const KEY: &str = "0x0123456789012345678901234567890123456789012345678901234567890123";
+
+    fn statuses(response: ExchangeOrderResponse) -> Vec<OrderStatus> {
+        match response {
+            ExchangeOrderResponse::Order(out) | ExchangeOrderResponse::Cancel(out) => out.statuses,
+            other => panic!("unexpected response {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_exchange_round_trip() {
+        let wallet = LocalWallet::signer(KEY.into());
+        let user = wallet.address();
+        let server = MockExchange::new(user)
+            .with_perp("ETH", 2000.0, 4)
+            .with_spot("UETH", 2000.0, 4)
+            .with_perp_balance(1000.0)
+            .with_spot_balance("USDC", 1000.0)
+            .serve()
+            .await
+            .unwrap();
+        let client = HyperliquidClient::new(server.network(), Box::new(wallet), user);
+
+        let markets = create_unified_market_info(
+            client.get_perp_info(None).await.unwrap(),
+            client.get_spot_info(None).await.unwrap(),
+        );
+        let spot = &markets.spot_markets["UETH"];
+        assert_eq!(spot.asset_id, 10000);
+
+        client.update_leverage(0, true, 5).await.unwrap();
+        // a rejected leverage change surfaces as an error
+        let err = client.update_leverage(0, true, 51).await.unwrap_err();
+        assert!(err.to_string().contains("Invalid leverage value"));
+        let out = client
+            .create_position_with_size(0, true, false, 2000.0, 0.5, false, 0.01, 4)
+            .await
+            .unwrap();
+        assert!(matches!(&statuses(out)[0], OrderStatus::Filled(fill) if fill.total_sz == "0.5"));
+        let out = client
+            .create_position_with_size(10000, false, true, 2000.0, 0.25, false, 0.01, 4)
+            .await
+            .unwrap();
+        assert!(matches!(&statuses(out)[0], OrderStatus::Filled(_)));
+
+        let perp = client.get_user_perp_info(None).await.unwrap();
+        let position = &perp.asset_positions[0].position;
+        assert_eq!(position.szi, "-0.5");
+        assert_eq!(position.leverage.value, 5);
+        assert_eq!(position.liquidation_px.as_deref(), Some("4000"));
+        let balances = client.get_user_spot_info(None).await.unwrap().balances;
+        assert_eq!(balances[0].total, "500");
+        assert_eq!(balances[1].total, "0.25");
+
+        // rests below the mark until the market trades down to it
+        let out = client
+            .create_position_with_size(0, true, true, 1900.0, 0.2, true, 0.0, 4)
+            .await
+            .unwrap();
+        let OrderStatus::Error(e) = &statuses(out)[0] else {
+            panic!("ioc order below the mark must not fill");
+        };
+        assert!(e.starts_with("Order could not immediately match"));
+        let out = client
+            .place_trigger_order(
+                0,
+                true,
+                "1900".into(),
+                "0.2".into(),
+                "1900".into(),
+                true,
+                TpSl::Tp,
+            )
+            .await
+            .unwrap();
+        assert!(matches!(&statuses(out)[0], OrderStatus::WaitingForTrigger));
+        assert_eq!(server.open_orders().len(), 1);
+        server.set_mark_px("ETH", 1850.0);
+        assert!(server.open_orders().is_empty());
+        let perp = client.get_user_perp_info(None).await.unwrap();
+        assert_eq!(perp.asset_positions[0].position.szi, "-0.3");
+        assert_eq!(
+            client.get_user_fills(false).await.unwrap()[0].closed_pnl,
+            "30"
+        );
+
+        let out = client
+            .create_position_raw(BulkOrder {
+                orders: vec![OrderRequest {
+                    asset: 0,
+                    is_buy: true,
+                    limit_px: "1500".into(),
+                    sz: "0.1".into(),
+                    reduce_only: false,
+                    order_type: OrderType::Limit(Limit { tif: "Gtc".into() }),
+                    cloid: None,
+                }],
+                grouping: Grouping::Na,
+                builder: None,
+            })
+            .await
+            .unwrap();
+        let OrderStatus::Resting(resting) = &statuses(out)[0] else {
+            panic!("gtc order below the mark must rest");
+        };
+        let oid = resting.oid as i64;
+        let out = client.cancel_order(oid, 0).await.unwrap();
+        assert!(matches!(&statuses(out)[0], OrderStatus::Success));
+        let out = client.cancel_order(oid, 0).await.unwrap();
+        assert!(matches!(&statuses(out)[0], OrderStatus::Error(_)));
+    }
+
+    #[tokio::test]
+    async fn test_mock_exchange_over_ws() {
+        let wallet = LocalWallet::signer(KEY.into());
+        let user = wallet.address();
+        let server = MockExchange::new(user)
+            .with_perp("ETH", 2000.0, 4)
+            .with_perp_balance(1000.0)
+            .serve()
+            .await
+            .unwrap();
+        let ws = HyperliquidWsClient::connect(server.network())
+            .await
+            .unwrap();
+        let client = HyperliquidClient::new(server.network(), Box::new(wallet), user).with_ws(ws);
+
+        let out = client
+            .create_position_with_size(0, true, false, 2000.0, 0.5, false, 0.01, 4)
+            .await
+            .unwrap();
+        assert!(matches!(&statuses(out)[0], OrderStatus::Filled(_)));
+        let perp = client.get_user_perp_info(None).await.unwrap();
+        assert_eq!(perp.asset_positions[0].position.szi, "-0.5");
+        assert_eq!(server.actions().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_exchange_rejects_unmodelled_actions() {
+        let wallet = LocalWallet::signer(KEY.into());
+        let user = wallet.address();
+        let server = MockExchange::new(user)
+            .with_perp("ETH", 2000.0, 4)
+            .with_perp_balance(1000.0)
+            .serve()
+            .await
+            .unwrap();
+        let client = HyperliquidClient::new(server.network(), Box::new(wallet), user);
+
+        let err = client
+            .place_twap(0, true, "0.5".into(), 30, false, false)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("twapOrder"));
+        assert!(
+            err.to_string()
+                .contains("not supported by the mock exchange")
+        );
+
+        let sub_account = client.for_vault_or_subaccount(Address::repeat_byte(0x22));
+        let err = sub_account
+            .create_position_with_size(0, true, false, 2000.0, 0.5, false, 0.01, 4)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("not supported by the mock exchange")
+        );
+        assert!(server.actions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mock_exchange_rejects_other_signers() {
+        let wallet = LocalWallet::signer(KEY.into());
+        let server = MockExchange::new(Address::repeat_byte(0x11))
+            .with_perp("ETH", 2000.0, 4)
+            .with_perp_balance(1000.0)
+            .serve()
+            .await
+            .unwrap();
+        let user = wallet.address();
+        let client = HyperliquidClient::new(server.network(), Box::new(wallet), user);
+
+        let err = client
+            .create_position_with_size(0, true, true, 2000.0, 0.1, false, 0.01, 4)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains(&format!("{:#x} does not exist", user))
+        );
+        assert!(server.actions().is_empty());
+    }
+}
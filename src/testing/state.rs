@@ -0,0 +1,913 @@
+use std::collections::{HashMap, HashSet};
+use std::time::SystemTime;
+
+use alloy::primitives::{Address, FixedBytes};
+use serde_json::Value;
+
+use crate::errors::Result;
+use crate::internal::*;
+use crate::market_info::*;
+use crate::order_responses::*;
+use crate::requests::*;
+use crate::signing::{recover_signer, recover_user_signer};
+use crate::user_data::*;
+use crate::utils::Network;
+
+const SPOT_ASSET_OFFSET: u32 = 10000;
+const USDC_TOKEN: i64 = 0;
+const MIN_ORDER_VALUE: f64 = 10.0;
+const DEFAULT_LEVERAGE: u32 = 20;
+
+#[derive(Debug, Clone)]
+pub(crate) struct MockMarket {
+    pub name: String,
+    pub sz_decimals: i64,
+    pub max_leverage: i64,
+    pub mark_px: f64,
+    pub funding: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct MockPosition {
+    szi: f64,
+    entry_px: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct MockHolding {
+    total: f64,
+    entry_ntl: f64,
+}
+
+#[derive(Debug, Clone)]
+struct MockOrder {
+    oid: u64,
+    asset: u32,
+    is_buy: bool,
+    limit_px: f64,
+    sz: f64,
+    reduce_only: bool,
+    trigger: Option<Trigger>,
+    cloid: Option<Cloid>,
+    timestamp: i64,
+}
+
+impl MockOrder {
+    /// whether the order executes with the market marked at `mark_px`
+    fn executes_at(&self, mark_px: f64) -> bool {
+        match &self.trigger {
+            Some(trigger) => {
+                let trigger_px: f64 = trigger.trigger_px.parse().unwrap_or_default();
+                match (trigger.tpsl, self.is_buy) {
+                    (TpSl::Tp, true) | (TpSl::Sl, false) => mark_px <= trigger_px,
+                    (TpSl::Tp, false) | (TpSl::Sl, true) => mark_px >= trigger_px,
+                }
+            }
+            None => crosses(self.is_buy, self.limit_px, mark_px),
+        }
+    }
+}
+
+/// Everything the mock exchange knows about its single account
+pub(crate) struct MockState {
+    pub signer: Address,
+    /// chain user-signed actions have to target
+    pub chain: Network,
+    pub perps: Vec<MockMarket>,
+    pub spots: Vec<MockMarket>,
+    pub perp_usdc: f64,
+    leverage: HashMap<u32, (bool, u32)>,
+    positions: HashMap<u32, MockPosition>,
+    holdings: HashMap<i64, MockHolding>,
+    resting: Vec<MockOrder>,
+    fills: Vec<Fills>,
+    used_nonces: HashSet<u64>,
+    next_oid: u64,
+    /// unix ms at which every resting order is cancelled
+    pub scheduled_cancel: Option<u64>,
+    pub actions: Vec<Value>,
+}
+
+fn crosses(is_buy: bool, limit_px: f64, mark_px: f64) -> bool {
+    if is_buy {
+        limit_px >= mark_px
+    } else {
+        limit_px <= mark_px
+    }
+}
+
+fn fmt_num(value: f64) -> String {
+    let rounded = (value * 1e8).round() / 1e8;
+    if rounded == 0.0 {
+        "0.0".to_string()
+    } else {
+        rounded.to_string()
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+fn ok(response: ExchangeOrderResponse) -> ExchangeResponse {
+    ExchangeResponse {
+        status: "ok".into(),
+        response: serde_json::to_value(response).unwrap(),
+    }
+}
+
+fn err(message: String) -> ExchangeResponse {
+    ExchangeResponse {
+        status: "err".into(),
+        response: Value::String(message),
+    }
+}
+
+fn unsupported(req: &ExchangeRequest) -> ExchangeResponse {
+    err(format!(
+        "{} is not supported by the mock exchange",
+        req.action["type"]
+    ))
+}
+
+impl MockState {
+    pub fn new(signer: Address) -> Self {
+        MockState {
+            signer,
+            chain: Network::Testnet,
+            perps: vec![],
+            spots: vec![],
+            perp_usdc: 0.0,
+            leverage: HashMap::new(),
+            positions: HashMap::new(),
+            holdings: HashMap::new(),
+            resting: vec![],
+            fills: vec![],
+            used_nonces: HashSet::new(),
+            next_oid: 1,
+            scheduled_cancel: None,
+            actions: vec![],
+        }
+    }
+
+    pub fn credit_spot(&mut self, coin: &str, amount: f64) {
+        let token = if coin == "USDC" {
+            Some(USDC_TOKEN)
+        } else {
+            self.spots
+                .iter()
+                .position(|market| market.name == coin)
+                .map(|index| index as i64 + 1)
+        };
+        let token = token.unwrap_or_else(|| panic!("{} is not listed on the mock exchange", coin));
+        let holding = self.holdings.entry(token).or_default();
+        holding.total += amount;
+        if token != USDC_TOKEN {
+            holding.entry_ntl += amount * self.spots[token as usize - 1].mark_px;
+        }
+    }
+
+    fn market(&self, asset: u32) -> Option<&MockMarket> {
+        if asset >= SPOT_ASSET_OFFSET {
+            self.spots.get((asset - SPOT_ASSET_OFFSET) as usize)
+        } else {
+            self.perps.get(asset as usize)
+        }
+    }
+
+    fn asset_of(&self, coin: &str) -> Option<u32> {
+        self.perps
+            .iter()
+            .position(|market| market.name == coin)
+            .map(|index| index as u32)
+            .or_else(|| {
+                self.spots
+                    .iter()
+                    .position(|market| market.name == coin)
+                    .map(|index| SPOT_ASSET_OFFSET + index as u32)
+            })
+    }
+
+    /// name fills and book snapshots use, spot pairs go by their `@index` alias
+    fn coin_of(&self, asset: u32) -> String {
+        if asset >= SPOT_ASSET_OFFSET {
+            format!("@{}", asset - SPOT_ASSET_OFFSET)
+        } else {
+            self.perps[asset as usize].name.clone()
+        }
+    }
+
+    fn mark_px(&self, asset: u32) -> f64 {
+        self.market(asset)
+            .map(|market| market.mark_px)
+            .unwrap_or_default()
+    }
+
+    fn leverage_of(&self, asset: u32) -> (bool, u32) {
+        self.leverage.get(&asset).copied().unwrap_or_else(|| {
+            let max = self.perps[asset as usize].max_leverage as u32;
+            (true, DEFAULT_LEVERAGE.min(max))
+        })
+    }
+
+    fn unrealized_pnl(&self, asset: u32, position: &MockPosition) -> f64 {
+        position.szi * (self.mark_px(asset) - position.entry_px)
+    }
+
+    fn account_value(&self) -> f64 {
+        self.perp_usdc
+            + self
+                .positions
+                .iter()
+                .map(|(asset, position)| self.unrealized_pnl(*asset, position))
+                .sum::<f64>()
+    }
+
+    fn margin_used(&self, asset: u32, szi: f64) -> f64 {
+        szi.abs() * self.mark_px(asset) / self.leverage_of(asset).1 as f64
+    }
+
+    fn available(&self, token: i64) -> f64 {
+        let total = self
+            .holdings
+            .get(&token)
+            .map(|h| h.total)
+            .unwrap_or_default();
+        let hold: f64 = self
+            .resting
+            .iter()
+            .filter(|order| order.asset >= SPOT_ASSET_OFFSET && order.trigger.is_none())
+            .filter_map(|order| {
+                let base = (order.asset - SPOT_ASSET_OFFSET) as i64 + 1;
+                match (order.is_buy, token) {
+                    (true, USDC_TOKEN) => Some(order.sz * order.limit_px),
+                    (false, t) if t == base => Some(order.sz),
+                    _ => None,
+                }
+            })
+            .sum();
+        total - hold
+    }
+
+    fn signer_of(&self, req: &ExchangeRequest, action: &Actions) -> Result<Address> {
+        if matches!(action, Actions::MultiSig(_)) || user_signed_action_params(action).is_some() {
+            return recover_user_signer(action, req.nonce, &req.signature, &self.chain);
+        }
+        recover_signer(action, req.nonce, &req.signature, &self.chain, None)
+    }
+
+    /// Cancels every resting order once a scheduled cancel is due. There is no timer, the
+    /// server runs this before serving each request.
+    pub fn expire_scheduled_cancel(&mut self) {
+        if self
+            .scheduled_cancel
+            .is_some_and(|time| now_ms() as u64 >= time)
+        {
+            self.scheduled_cancel = None;
+            self.resting.clear();
+        }
+    }
+
+    /// Authenticates and applies an action, answering the way `/exchange` does. Actions the
+    /// mock does not model fail instead of reporting a success that changed nothing, and so do
+    /// actions for a vault or sub-account since the mock holds a single account.
+    pub fn execute(&mut self, req: ExchangeRequest, action: Actions) -> ExchangeResponse {
+        let supported = matches!(
+            action,
+            Actions::Order(_)
+                | Actions::Cancel(_)
+                | Actions::CancelByCloid(_)
+                | Actions::UpdateLeverage(_)
+                | Actions::UsdClassTransfer(_)
+                | Actions::ScheduleCancel(_)
+        );
+        if !supported {
+            return unsupported(&req);
+        }
+        if let Some(vault) = &req.vault_address {
+            return err(format!(
+                "trading for vault or sub-account {} is not supported by the mock exchange",
+                vault
+            ));
+        }
+
+        match self.signer_of(&req, &action) {
+            Ok(signer) if signer == self.signer => {}
+            Ok(signer) => return err(format!("User or API Wallet {:#x} does not exist.", signer)),
+            Err(e) => return err(format!("Invalid signature: {}", e)),
+        }
+        if !self.used_nonces.insert(req.nonce) {
+            return err(format!("Nonce {} already used", req.nonce));
+        }
+
+        let response = match action {
+            Actions::Order(bulk) => {
+                let statuses = bulk.orders.iter().map(|order| self.place(order)).collect();
+                ok(ExchangeOrderResponse::Order(OrderStatusResponse {
+                    statuses,
+                }))
+            }
+            Actions::Cancel(bulk) => {
+                let statuses = bulk
+                    .cancels
+                    .iter()
+                    .map(|cancel| self.cancel(cancel.asset, |order| order.oid as i64 == cancel.oid))
+                    .collect();
+                ok(ExchangeOrderResponse::Cancel(OrderStatusResponse {
+                    statuses,
+                }))
+            }
+            Actions::CancelByCloid(bulk) => {
+                let statuses = bulk
+                    .cancels
+                    .iter()
+                    .map(|cancel| {
+                        self.cancel(cancel.asset, |order| {
+                            order.cloid.as_ref() == Some(&cancel.cloid)
+                        })
+                    })
+                    .collect();
+                ok(ExchangeOrderResponse::Cancel(OrderStatusResponse {
+                    statuses,
+                }))
+            }
+            Actions::UpdateLeverage(update) => match self.perps.get(update.asset as usize) {
+                Some(market)
+                    if update.leverage >= 1 && update.leverage as i64 <= market.max_leverage =>
+                {
+                    self.leverage
+                        .insert(update.asset, (update.is_cross, update.leverage));
+                    ok(ExchangeOrderResponse::Default)
+                }
+                _ => err("Invalid leverage value".into()),
+            },
+            Actions::UsdClassTransfer(transfer) => match self.transfer_usd(&transfer) {
+                Ok(()) => ok(ExchangeOrderResponse::Default),
+                Err(e) => err(e),
+            },
+            Actions::ScheduleCancel(ScheduleCancel { time }) => match time {
+                Some(time) if time < now_ms() as u64 + MIN_SCHEDULE_CANCEL_DELAY_MS => err(
+                    "Scheduled cancel time too early, must be at least 5 seconds from now.".into(),
+                ),
+                time => {
+                    self.scheduled_cancel = time;
+                    ok(ExchangeOrderResponse::Default)
+                }
+            },
+            _ => unsupported(&req),
+        };
+
+        if response.status == "ok" {
+            self.actions.push(req.action);
+        }
+        response
+    }
+
+    fn transfer_usd(&mut self, transfer: &TransferRequest) -> std::result::Result<(), String> {
+        let amount: f64 = transfer
+            .amount
+            .split(' ')
+            .next()
+            .and_then(|amount| amount.parse().ok())
+            .ok_or_else(|| "Invalid amount".to_string())?;
+        if transfer.to_perp {
+            if self.available(USDC_TOKEN) < amount {
+                return Err("Insufficient balance for transfer".into());
+            }
+            self.holdings.entry(USDC_TOKEN).or_default().total -= amount;
+            self.perp_usdc += amount;
+        } else {
+            let margin: f64 = self
+                .positions
+                .iter()
+                .map(|(asset, position)| self.margin_used(*asset, position.szi))
+                .sum();
+            if self.account_value() - margin < amount {
+                return Err("Insufficient balance for transfer".into());
+            }
+            self.perp_usdc -= amount;
+            self.holdings.entry(USDC_TOKEN).or_default().total += amount;
+        }
+        Ok(())
+    }
+
+    fn cancel(&mut self, asset: u32, matches: impl Fn(&MockOrder) -> bool) -> OrderStatus {
+        match self
+            .resting
+            .iter()
+            .position(|order| order.asset == asset && matches(order))
+        {
+            Some(index) => {
+                self.resting.remove(index);
+                OrderStatus::Success
+            }
+            None => OrderStatus::Error(format!(
+                "Order was never placed, already canceled, or filled. asset={}",
+                asset
+            )),
+        }
+    }
+
+    fn place(&mut self, req: &OrderRequest) -> OrderStatus {
+        let Some(market) = self.market(req.asset) else {
+            return OrderStatus::Error(format!("Invalid asset {}", req.asset));
+        };
+        let mark_px = market.mark_px;
+        let (Ok(limit_px), Ok(sz)) = (req.limit_px.parse::<f64>(), req.sz.parse::<f64>()) else {
+            return OrderStatus::Error("Order has invalid price or size.".into());
+        };
+        if sz <= 0.0 {
+            return OrderStatus::Error("Order has zero size.".into());
+        }
+        if req.reduce_only && !self.reduces(req.asset, req.is_buy) {
+            return OrderStatus::Error("Reduce only order would increase position.".into());
+        }
+
+        let order = MockOrder {
+            oid: self.next_oid,
+            asset: req.asset,
+            is_buy: req.is_buy,
+            limit_px,
+            sz,
+            reduce_only: req.reduce_only,
+            trigger: None,
+            cloid: req.cloid.clone(),
+            timestamp: now_ms(),
+        };
+        self.next_oid += 1;
+
+        let tif = match &req.order_type {
+            OrderType::Trigger(trigger) => {
+                self.resting.push(MockOrder {
+                    trigger: Some(trigger.clone()),
+                    ..order
+                });
+                return OrderStatus::WaitingForTrigger;
+            }
+            OrderType::Limit(limit) => limit.tif.as_str(),
+        };
+
+        if !req.reduce_only && sz * mark_px < MIN_ORDER_VALUE {
+            return OrderStatus::Error(format!(
+                "Order must have minimum value of ${}. asset={}",
+                MIN_ORDER_VALUE, req.asset
+            ));
+        }
+
+        let marketable = crosses(req.is_buy, limit_px, mark_px);
+        match (tif, marketable) {
+            ("Alo", true) => OrderStatus::Error(format!(
+                "Post only order would have immediately matched, bbo was {}@{}. asset={}",
+                fmt_num(mark_px),
+                fmt_num(mark_px),
+                req.asset
+            )),
+            (_, true) => match self.fill(&order, mark_px) {
+                Ok(total_sz) => OrderStatus::Filled(FilledOrder {
+                    total_sz: fmt_num(total_sz),
+                    avg_px: fmt_num(mark_px),
+                    oid: order.oid,
+                }),
+                Err(e) => OrderStatus::Error(e),
+            },
+            ("Ioc" | "FrontendMarket", false) => OrderStatus::Error(format!(
+                "Order could not immediately match against any resting orders. asset={}",
+                req.asset
+            )),
+            (_, false) => {
+                if req.asset >= SPOT_ASSET_OFFSET {
+                    let (token, needed) = if req.is_buy {
+                        (USDC_TOKEN, sz * limit_px)
+                    } else {
+                        ((req.asset - SPOT_ASSET_OFFSET) as i64 + 1, sz)
+                    };
+                    if self.available(token) < needed {
+                        return OrderStatus::Error(format!(
+                            "Insufficient spot balance asset={}",
+                            req.asset
+                        ));
+                    }
+                }
+                let oid = order.oid;
+                self.resting.push(order);
+                OrderStatus::Resting(RestingOrder { oid })
+            }
+        }
+    }
+
+    /// whether an order on the given side shrinks the current perp position
+    fn reduces(&self, asset: u32, is_buy: bool) -> bool {
+        self.positions
+            .get(&asset)
+            .is_some_and(|position| (position.szi < 0.0) == is_buy)
+    }
+
+    /// Executes `order` at `px`, returning the filled size
+    fn fill(&mut self, order: &MockOrder, px: f64) -> std::result::Result<f64, String> {
+        let (sz, start_position, closed_pnl, dir) = if order.asset >= SPOT_ASSET_OFFSET {
+            self.fill_spot(order, px)?
+        } else {
+            self.fill_perp(order, px)?
+        };
+
+        self.fills.push(Fills {
+            closed_pnl: fmt_num(closed_pnl),
+            coin: self.coin_of(order.asset),
+            crossed: true,
+            dir,
+            hash: format!("{:#x}", FixedBytes::<32>::ZERO),
+            oid: order.oid as i64,
+            px: fmt_num(px),
+            side: if order.is_buy { "B" } else { "A" }.into(),
+            start_position: fmt_num(start_position),
+            sz: fmt_num(sz),
+            time: now_ms(),
+            fee: "0.0".into(),
+            fee_token: "USDC".into(),
+            builder_fee: None,
+            tid: self.fills.len() as i64 + 1,
+        });
+        Ok(sz)
+    }
+
+    fn fill_spot(
+        &mut self,
+        order: &MockOrder,
+        px: f64,
+    ) -> std::result::Result<(f64, f64, f64, String), String> {
+        let token = (order.asset - SPOT_ASSET_OFFSET) as i64 + 1;
+        let start = self
+            .holdings
+            .get(&token)
+            .map(|h| h.total)
+            .unwrap_or_default();
+        let insufficient = || format!("Insufficient spot balance asset={}", order.asset);
+
+        if order.is_buy {
+            if self.available(USDC_TOKEN) < order.sz * px {
+                return Err(insufficient());
+            }
+            self.holdings.entry(USDC_TOKEN).or_default().total -= order.sz * px;
+            let holding = self.holdings.entry(token).or_default();
+            holding.total += order.sz;
+            holding.entry_ntl += order.sz * px;
+        } else {
+            if self.available(token) < order.sz {
+                return Err(insufficient());
+            }
+            let holding = self.holdings.entry(token).or_default();
+            holding.entry_ntl -= holding.entry_ntl * order.sz / holding.total;
+            holding.total -= order.sz;
+            self.holdings.entry(USDC_TOKEN).or_default().total += order.sz * px;
+        }
+
+        let dir = if order.is_buy { "Buy" } else { "Sell" };
+        Ok((order.sz, start, 0.0, dir.into()))
+    }
+
+    fn fill_perp(
+        &mut self,
+        order: &MockOrder,
+        px: f64,
+    ) -> std::result::Result<(f64, f64, f64, String), String> {
+        let current = self
+            .positions
+            .get(&order.asset)
+            .cloned()
+            .unwrap_or_default();
+        let start = current.szi;
+        let sz = if order.reduce_only {
+            order.sz.min(start.abs())
+        } else {
+            order.sz
+        };
+        let signed = if order.is_buy { sz } else { -sz };
+
+        let mut next = MockPosition {
+            szi: ((start + signed) * 1e8).round() / 1e8,
+            entry_px: current.entry_px,
+        };
+        let mut closed_pnl = 0.0;
+        if start == 0.0 || start.signum() == signed.signum() {
+            next.entry_px = (start.abs() * current.entry_px + sz * px) / (start.abs() + sz);
+        } else {
+            closed_pnl = sz.min(start.abs()) * (px - current.entry_px) * start.signum();
+            if sz > start.abs() {
+                next.entry_px = px;
+            }
+        }
+
+        let increases = next.szi.abs() > start.abs();
+        let required: f64 = self
+            .positions
+            .iter()
+            .filter(|(asset, _)| **asset != order.asset)
+            .map(|(asset, position)| self.margin_used(*asset, position.szi))
+            .sum::<f64>()
+            + self.margin_used(order.asset, next.szi);
+        if increases && required > self.account_value() {
+            return Err(format!(
+                "Insufficient margin to place order. asset={}",
+                order.asset
+            ));
+        }
+
+        self.perp_usdc += closed_pnl;
+        if next.szi == 0.0 {
+            self.positions.remove(&order.asset);
+        } else {
+            self.positions.insert(order.asset, next);
+        }
+
+        let dir = match (start > 0.0 || (start == 0.0 && order.is_buy), order.is_buy) {
+            (true, true) => "Open Long",
+            (true, false) => "Close Long",
+            (false, false) => "Open Short",
+            (false, true) => "Close Short",
+        };
+        Ok((sz, start, closed_pnl, dir.into()))
+    }
+
+    /// Moves a market and executes every resting or trigger order the new mark reaches
+    pub fn set_mark_px(&mut self, coin: &str, mark_px: f64) {
+        let asset = self
+            .asset_of(coin)
+            .unwrap_or_else(|| panic!("{} is not listed on the mock exchange", coin));
+        if asset >= SPOT_ASSET_OFFSET {
+            self.spots[(asset - SPOT_ASSET_OFFSET) as usize].mark_px = mark_px;
+        } else {
+            self.perps[asset as usize].mark_px = mark_px;
+        }
+
+        let (triggered, resting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.resting)
+            .into_iter()
+            .partition(|order| order.asset == asset && order.executes_at(mark_px));
+        self.resting = resting;
+        for order in triggered {
+            let px = if order.trigger.is_some() {
+                mark_px
+            } else {
+                order.limit_px
+            };
+            if order.reduce_only && !self.reduces(order.asset, order.is_buy) {
+                continue;
+            }
+            // like the exchange, an order that can no longer be executed is dropped
+            let _ = self.fill(&order, px);
+        }
+    }
+
+    pub fn fills(&self) -> Vec<Fills> {
+        self.fills.iter().rev().cloned().collect()
+    }
+
+    pub fn open_orders(&self) -> Vec<OpenOrder> {
+        self.resting
+            .iter()
+            .rev()
+            .map(|order| OpenOrder {
+                coin: self.coin_of(order.asset),
+                limit_px: fmt_num(order.limit_px),
+                oid: order.oid as i64,
+                side: if order.is_buy { "B" } else { "A" }.into(),
+                sz: fmt_num(order.sz),
+                timestamp: order.timestamp,
+            })
+            .collect()
+    }
+
+    pub fn perp_info(&self) -> PerpetualsInfo {
+        let (universe, metadata) = self
+            .perps
+            .iter()
+            .map(|market| {
+                (
+                    Universe {
+                        name: market.name.clone(),
+                        sz_decimals: market.sz_decimals,
+                        max_leverage: market.max_leverage,
+                        ..Default::default()
+                    },
+                    PerpetualMetadata {
+                        funding: fmt_num(market.funding),
+                        mark_px: fmt_num(market.mark_px),
+                        mid_px: Some(fmt_num(market.mark_px)),
+                        oracle_px: fmt_num(market.mark_px),
+                        prev_day_px: fmt_num(market.mark_px),
+                        impact_pxs: Some(vec![fmt_num(market.mark_px), fmt_num(market.mark_px)]),
+                        premium: Some("0.0".into()),
+                        day_ntl_vlm: "0.0".into(),
+                        open_interest: "0.0".into(),
+                        day_base_vlm: "0.0".into(),
+                    },
+                )
+            })
+            .unzip();
+        (UniverseInfo { universe }, metadata)
+    }
+
+    pub fn spot_info(&self) -> SpotResponse {
+        let mut tokens = vec![Token {
+            name: "USDC".into(),
+            sz_decimals: 8,
+            wei_decimals: 8,
+            index: USDC_TOKEN,
+            token_id: format!("{:#034x}", USDC_TOKEN),
+            is_canonical: true,
+            full_name: None,
+            deployer_trading_fee_share: "0.0".into(),
+        }];
+        let mut universe = vec![];
+        let mut market_data = vec![];
+        for (index, market) in self.spots.iter().enumerate() {
+            let token = index as i64 + 1;
+            tokens.push(Token {
+                name: market.name.clone(),
+                sz_decimals: market.sz_decimals,
+                wei_decimals: market.sz_decimals + 3,
+                index: token,
+                token_id: format!("{:#034x}", token),
+                is_canonical: true,
+                full_name: None,
+                deployer_trading_fee_share: "0.0".into(),
+            });
+            universe.push(SpotUniverse {
+                tokens: vec![token, USDC_TOKEN],
+                name: format!("@{}", index),
+                index: index as i64,
+                is_canonical: true,
+            });
+            market_data.push(MarketData {
+                coin: format!("@{}", index),
+                mark_px: fmt_num(market.mark_px),
+                mid_px: Some(fmt_num(market.mark_px)),
+                prev_day_px: fmt_num(market.mark_px),
+                day_ntl_vlm: "0.0".into(),
+                circulating_supply: "0.0".into(),
+                total_supply: "0.0".into(),
+                day_base_vlm: "0.0".into(),
+            });
+        }
+        SpotResponse(SpotInfo { universe, tokens }, market_data)
+    }
+
+    pub fn user_perp(&self) -> UserPerpPosition {
+        let mut assets: Vec<_> = self.positions.keys().copied().collect();
+        assets.sort();
+
+        let account_value = self.account_value();
+        let mut total_margin_used = 0.0;
+        let mut total_ntl_pos = 0.0;
+        let mut asset_positions = vec![];
+        for asset in assets {
+            let position = &self.positions[&asset];
+            let market = &self.perps[asset as usize];
+            let (is_cross, leverage) = self.leverage_of(asset);
+            let upnl = self.unrealized_pnl(asset, position);
+            let margin_used = self.margin_used(asset, position.szi);
+            // price at which the account value is exhausted, maintenance margin is ignored
+            let liquidation_px = position.entry_px - (account_value - upnl) / position.szi;
+            total_margin_used += margin_used;
+            total_ntl_pos += position.szi.abs() * market.mark_px;
+
+            asset_positions.push(AssetPosition {
+                type_field: "oneWay".into(),
+                position: Position {
+                    coin: market.name.clone(),
+                    cum_funding: CumFunding {
+                        all_time: "0.0".into(),
+                        since_change: "0.0".into(),
+                        since_open: "0.0".into(),
+                    },
+                    entry_px: fmt_num(position.entry_px),
+                    leverage: Leverage {
+                        type_field: if is_cross { "cross" } else { "isolated" }.into(),
+                        value: leverage as i64,
+                    },
+                    liquidation_px: (liquidation_px > 0.0).then(|| fmt_num(liquidation_px)),
+                    margin_used: fmt_num(margin_used),
+                    max_leverage: market.max_leverage,
+                    position_value: fmt_num(position.szi.abs() * market.mark_px),
+                    return_on_equity: fmt_num(
+                        upnl * leverage as f64 / (position.szi.abs() * position.entry_px),
+                    ),
+                    szi: fmt_num(position.szi),
+                    unrealized_pnl: fmt_num(upnl),
+                },
+            });
+        }
+
+        let total_raw_usd = account_value
+            - self
+                .positions
+                .iter()
+                .map(|(asset, position)| position.szi * self.mark_px(*asset))
+                .sum::<f64>();
+        UserPerpPosition {
+            asset_positions,
+            cross_maintenance_margin_used: "0.0".into(),
+            cross_margin_summary: CrossMarginSummary {
+                account_value: fmt_num(account_value),
+                total_margin_used: fmt_num(total_margin_used),
+                total_ntl_pos: fmt_num(total_ntl_pos),
+                total_raw_usd: fmt_num(total_raw_usd),
+            },
+            margin_summary: MarginSummary {
+                account_value: fmt_num(account_value),
+                total_margin_used: fmt_num(total_margin_used),
+                total_ntl_pos: fmt_num(total_ntl_pos),
+                total_raw_usd: fmt_num(total_raw_usd),
+            },
+            time: now_ms(),
+            withdrawable: fmt_num((account_value - total_margin_used).max(0.0)),
+        }
+    }
+
+    pub fn user_spot(&self) -> UserSpotPosition {
+        let mut tokens: Vec<_> = self.holdings.keys().copied().collect();
+        tokens.sort();
+        let balances = tokens
+            .into_iter()
+            .map(|token| {
+                let holding = &self.holdings[&token];
+                Balance {
+                    coin: if token == USDC_TOKEN {
+                        "USDC".into()
+                    } else {
+                        self.spots[token as usize - 1].name.clone()
+                    },
+                    token,
+                    hold: fmt_num(holding.total - self.available(token)),
+                    total: fmt_num(holding.total),
+                    entry_ntl: fmt_num(holding.entry_ntl),
+                }
+            })
+            .collect();
+        UserSpotPosition { balances }
+    }
+
+    pub fn l2_book(&self, coin: &str) -> Option<L2Book> {
+        let asset = self.asset_of(coin).or_else(|| {
+            coin.strip_prefix('@')
+                .and_then(|index| index.parse::<u32>().ok())
+                .filter(|index| (*index as usize) < self.spots.len())
+                .map(|index| SPOT_ASSET_OFFSET + index)
+        })?;
+
+        let side = |is_buy: bool| {
+            let mut levels: Vec<(f64, f64, u64)> = vec![];
+            for order in self
+                .resting
+                .iter()
+                .filter(|order| order.asset == asset && order.is_buy == is_buy)
+                .filter(|order| order.trigger.is_none())
+            {
+                match levels.iter_mut().find(|level| level.0 == order.limit_px) {
+                    Some(level) => {
+                        level.1 += order.sz;
+                        level.2 += 1;
+                    }
+                    None => levels.push((order.limit_px, order.sz, 1)),
+                }
+            }
+            levels.sort_by(|a, b| {
+                if is_buy {
+                    b.0.total_cmp(&a.0)
+                } else {
+                    a.0.total_cmp(&b.0)
+                }
+            });
+            levels
+                .into_iter()
+                .map(|(px, sz, n)| Level {
+                    px: fmt_num(px),
+                    sz: fmt_num(sz),
+                    n,
+                })
+                .collect()
+        };
+
+        Some(L2Book {
+            coin: self.coin_of(asset),
+            time: now_ms() as u64,
+            levels: [side(true), side(false)],
+        })
+    }
+
+    pub fn all_mids(&self) -> HashMap<String, String> {
+        self.perps
+            .iter()
+            .enumerate()
+            .map(|(index, market)| (self.coin_of(index as u32), fmt_num(market.mark_px)))
+            .chain(self.spots.iter().enumerate().map(|(index, market)| {
+                (
+                    self.coin_of(SPOT_ASSET_OFFSET + index as u32),
+                    fmt_num(market.mark_px),
+                )
+            }))
+            .collect()
+    }
+}
@@ -1,5 +1,8 @@
 use crate::errors::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::requests::OrderRef;
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -125,6 +128,7 @@ pub struct Delta {
 }
 
 #[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GetUserFundingHistoryReq {
     #[serde(rename = "type")]
     pub request_type: String,
@@ -158,6 +162,69 @@ pub struct GetUserFills {
     pub aggregate_by_time: bool,
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetUserFillsByTime {
+    #[serde(rename = "type")]
+    pub request_type: String,
+    pub user: String,
+    pub start_time: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<u64>,
+    pub aggregate_by_time: bool,
+}
+
+/// Shared by the time windowed user queries (`userNonFundingLedgerUpdates`, ...)
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetUserHistoryByTime {
+    #[serde(rename = "type")]
+    pub request_type: String,
+    pub user: String,
+    pub start_time: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<u64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerUpdate {
+    pub time: i64,
+    pub hash: String,
+    pub delta: LedgerDelta,
+}
+
+impl LedgerUpdate {
+    /// Tells the updates of one millisecond apart. Several deltas of one tx, or system entries
+    /// with a zero hash, share a hash, so the whole delta is part of the key.
+    pub(crate) fn key(&self) -> (String, String, Option<String>, BTreeMap<String, String>) {
+        let details = self
+            .delta
+            .details
+            .iter()
+            .map(|(field, value)| (field.clone(), value.to_string()))
+            .collect();
+        (
+            self.hash.clone(),
+            self.delta.type_field.clone(),
+            self.delta.usdc.clone(),
+            details,
+        )
+    }
+}
+
+/// `type_field` is e.g. `deposit`, `withdraw`, `internalTransfer`, `spotTransfer`,
+/// `subAccountTransfer` or `vaultDeposit`, the remaining fields depend on it
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerDelta {
+    #[serde(rename = "type")]
+    pub type_field: String,
+    pub usdc: Option<String>,
+    #[serde(flatten)]
+    pub details: HashMap<String, serde_json::Value>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GetHistoricalOrders {
     #[serde(rename = "type")]
@@ -200,16 +267,95 @@ pub struct Fills {
     pub tid: i64,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct GetUserTwapSliceFills {
+    #[serde(rename = "type")]
+    pub request_type: String,
+    pub user: String,
+}
+
+pub type UserTwapSliceFillsResponse = Vec<TwapSliceFill>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwapSliceFill {
+    pub fill: Fills,
+    pub twap_id: u64,
+}
+
 pub type UserOrderHistoryResponse = Vec<OrderWithStatus>;
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OrderWithStatus {
     pub order: UserOrder,
-    pub status: String,
+    pub status: OrderState,
     pub status_timestamp: i64,
 }
 
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OrderState {
+    #[default]
+    Open,
+    Filled,
+    Canceled,
+    Triggered,
+    Rejected,
+    MarginCanceled,
+    VaultWithdrawalCanceled,
+    OpenInterestCapCanceled,
+    SelfTradeCanceled,
+    ReduceOnlyCanceled,
+    SiblingFilledCanceled,
+    DelistedCanceled,
+    LiquidatedCanceled,
+    ScheduledCancel,
+    TickRejected,
+    MinTradeNtlRejected,
+    PerpMarginRejected,
+    ReduceOnlyRejected,
+    BadAloPxRejected,
+    IocCancelRejected,
+    BadTriggerPxRejected,
+    MarketOrderNoLiquidityRejected,
+    PositionIncreaseAtOpenInterestCapRejected,
+    PositionFlipAtOpenInterestCapRejected,
+    TooAggressiveAtOpenInterestCapRejected,
+    OpenInterestIncreaseRejected,
+    InsufficientSpotBalanceRejected,
+    OracleRejected,
+    PerpMaxPositionRejected,
+    /// a status added to the exchange after this enum was written
+    #[serde(other)]
+    Unknown,
+}
+
+impl OrderState {
+    /// whether the order can still fill
+    pub fn is_live(&self) -> bool {
+        matches!(self, OrderState::Open | OrderState::Triggered)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetOrderStatus {
+    #[serde(rename = "type")]
+    pub request_type: String,
+    pub user: String,
+    pub oid: OrderRef,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum OrderStatusLookup {
+    Order {
+        order: Box<OrderWithStatus>,
+    },
+    /// no order with that oid or cloid exists for the user
+    UnknownOid,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UserOrder {
@@ -222,15 +368,92 @@ pub struct UserOrder {
     pub trigger_condition: String,
     pub is_trigger: bool,
     pub trigger_px: String,
-    pub children: Vec<serde_json::Value>,
+    pub children: Vec<UserOrder>,
     pub is_position_tpsl: bool,
     pub reduce_only: bool,
     pub order_type: String,
     pub orig_sz: String,
-    pub tif: String,
+    pub tif: Option<String>,
     pub cloid: Option<String>,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct GetSubAccounts {
+    #[serde(rename = "type")]
+    pub request_type: String,
+    pub user: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubAccount {
+    pub name: String,
+    pub sub_account_user: String,
+    pub master: String,
+    pub clearinghouse_state: UserPerpPosition,
+    pub spot_state: UserSpotPosition,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetVaultDetails {
+    #[serde(rename = "type")]
+    pub request_type: String,
+    pub vault_address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultFollower {
+    pub user: String,
+    pub vault_equity: String,
+    pub pnl: String,
+    pub all_time_pnl: String,
+    pub days_following: i64,
+    pub vault_entry_time: i64,
+    pub lockup_until: i64,
+}
+
+/// `follower_state` is only present when the request named a `user` following the vault
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultDetails {
+    pub name: String,
+    pub vault_address: String,
+    pub leader: String,
+    pub description: String,
+    pub portfolio: serde_json::Value,
+    pub apr: f64,
+    pub follower_state: Option<VaultFollower>,
+    pub leader_fraction: f64,
+    pub leader_commission: f64,
+    pub followers: Vec<VaultFollower>,
+    pub max_distributable: f64,
+    pub max_withdrawable: f64,
+    pub is_closed: bool,
+    pub allow_deposits: bool,
+    pub always_close_on_withdraw: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetUserVaultEquities {
+    #[serde(rename = "type")]
+    pub request_type: String,
+    pub user: String,
+}
+
+pub type UserVaultEquitiesResponse = Vec<UserVaultEquity>;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserVaultEquity {
+    pub vault_address: String,
+    pub equity: String,
+    pub locked_until_timestamp: Option<i64>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GetUserMultiSigConfig {
     #[serde(rename = "type")]
@@ -244,3 +467,129 @@ pub struct UserMultiSigConfig {
     pub authorized_users: Vec<String>,
     pub threshold: u64,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_order_status_lookup() {
+        let raw = r#"{
+            "status":"order",
+            "order":{
+                "order":{
+                    "coin":"ETH","side":"A","limitPx":"2412.7","sz":"0.0","oid":1,
+                    "timestamp":1724361546645,"triggerCondition":"N/A","isTrigger":false,
+                    "triggerPx":"0.0","children":[],"isPositionTpsl":false,"reduceOnly":true,
+                    "orderType":"Market","origSz":"0.0076","tif":"FrontendMarket","cloid":null
+                },
+                "status":"filled",
+                "statusTimestamp":1724361546645
+            }
+        }"#;
+        let OrderStatusLookup::Order { order } = serde_json::from_str(raw).unwrap() else {
+            panic!("expected an order");
+        };
+        assert_eq!(order.status, OrderState::Filled);
+        assert!(!order.status.is_live());
+
+        let unknown: OrderStatusLookup =
+            serde_json::from_str(r#"{"status":"unknownOid"}"#).unwrap();
+        assert_eq!(unknown, OrderStatusLookup::UnknownOid);
+
+        let state: OrderState = serde_json::from_str(r#""someFutureRejected""#).unwrap();
+        assert_eq!(state, OrderState::Unknown);
+
+        let req = GetOrderStatus {
+            request_type: "orderStatus".into(),
+            user: "0x1".into(),
+            oid: OrderRef::Cloid(crate::Cloid::from_u128(7)),
+        };
+        assert_eq!(
+            serde_json::to_value(&req).unwrap()["oid"],
+            "0x00000000000000000000000000000007"
+        );
+    }
+
+    #[test]
+    fn test_ledger_update_key() {
+        let raw = r#"[
+            {"time":1700000000000,"hash":"0x00","delta":{"type":"vaultDistribution","vault":"0x1111111111111111111111111111111111111111","usdc":"12.5"}},
+            {"time":1700000000000,"hash":"0x00","delta":{"type":"vaultDistribution","vault":"0x2222222222222222222222222222222222222222","usdc":"12.5"}},
+            {"time":1700000000000,"hash":"0x00","delta":{"usdc":"12.5","vault":"0x1111111111111111111111111111111111111111","type":"vaultDistribution"}}
+        ]"#;
+        let updates: Vec<LedgerUpdate> = serde_json::from_str(raw).unwrap();
+        // same hash and amount, different vault
+        assert_ne!(updates[0].key(), updates[1].key());
+        // the same entry listed again, field order does not matter
+        assert_eq!(updates[0].key(), updates[2].key());
+    }
+
+    #[test]
+    fn test_vault_details() {
+        // HLP as returned without a `user`, trimmed to one portfolio window and one follower
+        let raw = r#"{
+            "name":"Hyperliquidity Provider (HLP)",
+            "vaultAddress":"0xdfc24b077bc1425ad1dea75bcb6f8158e10df303",
+            "leader":"0x677d831aef5328190852e24f13c46cac05f984e7",
+            "description":"This community-owned vault provides liquidity to Hyperliquid.",
+            "portfolio":[["day",{"accountValueHistory":[[1734397526634,"329265410.90790099"]],"pnlHistory":[[1734397526634,"0.0"]],"vlm":"0.0"}]],
+            "apr":0.36387129259090006,
+            "followerState":null,
+            "leaderFraction":0.0007904828725729887,
+            "leaderCommission":0,
+            "followers":[{
+                "user":"0x005844b2ffb2e122cf4244be7dbcb4f84924907c","vaultEquity":"714491.71026243",
+                "pnl":"3203.43026143","allTimePnl":"79843.74476743","daysFollowing":388,
+                "vaultEntryTime":1700926145201,"lockupUntil":1734824439201
+            }],
+            "maxDistributable":94856870.164485,
+            "maxWithdrawable":742557.680863,
+            "isClosed":false,
+            "relationship":{"type":"parent","data":{"childAddresses":["0x010461c14e146ac35fe42271bdc1134ee31c703a"]}},
+            "allowDeposits":true,
+            "alwaysCloseOnWithdraw":false
+        }"#;
+        let details: VaultDetails = serde_json::from_str(raw).unwrap();
+        assert_eq!(details.leader, "0x677d831aef5328190852e24f13c46cac05f984e7");
+        assert_eq!(details.leader_commission, 0.0);
+        assert_eq!(details.follower_state, None);
+        assert_eq!(details.followers[0].days_following, 388);
+        assert_eq!(details.portfolio[0][0], "day");
+        assert!(details.allow_deposits && !details.is_closed);
+
+        // asked for a follower, `followerState` is filled in; without one it may be left out
+        let mut value: serde_json::Value = serde_json::from_str(raw).unwrap();
+        value["followerState"] = value["followers"][0].clone();
+        let details: VaultDetails = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(
+            details.follower_state.unwrap().vault_equity,
+            "714491.71026243"
+        );
+        value.as_object_mut().unwrap().remove("followerState");
+        let details: VaultDetails = serde_json::from_value(value).unwrap();
+        assert_eq!(details.follower_state, None);
+    }
+
+    #[test]
+    fn test_user_vault_equities() {
+        let raw = r#"[
+            {"vaultAddress":"0xdfc24b077bc1425ad1dea75bcb6f8158e10df303","equity":"742500.082809","lockedUntilTimestamp":1734824439201},
+            {"vaultAddress":"0x010461c14e146ac35fe42271bdc1134ee31c703a","equity":"12.5"}
+        ]"#;
+        let equities: UserVaultEquitiesResponse = serde_json::from_str(raw).unwrap();
+        assert_eq!(equities.len(), 2);
+        assert_eq!(equities[0].equity, "742500.082809");
+        assert_eq!(equities[0].locked_until_timestamp, Some(1734824439201));
+        assert_eq!(equities[1].locked_until_timestamp, None);
+
+        let req = GetUserVaultEquities {
+            request_type: "userVaultEquities".into(),
+            user: "0x1".into(),
+        };
+        assert_eq!(
+            serde_json::to_string(&req).unwrap(),
+            r#"{"type":"userVaultEquities","user":"0x1"}"#
+        );
+    }
+}
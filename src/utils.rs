@@ -1,29 +1,64 @@
 use crate::errors::Result;
+use futures::{Stream, StreamExt, TryStreamExt, stream};
+use std::collections::HashSet;
+use std::future::Future;
+use std::hash::Hash;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Network {
     Mainnet,
     Testnet,
+    /// Any other endpoint such as a private node, a proxy or a local mock. `chain_name` is the
+    /// `hyperliquidChain` put in user-signed actions and `is_mainnet` picks the L1 agent source,
+    /// both must match the chain the endpoint forwards to for signatures to verify.
+    Custom {
+        api_url: String,
+        ws_url: String,
+        chain_name: String,
+        is_mainnet: bool,
+    },
 }
 
 impl Network {
-    pub fn name(self) -> String {
+    pub fn name(&self) -> String {
         match self {
             Network::Mainnet => "Mainnet".to_string(),
             Network::Testnet => "Testnet".to_string(),
+            Network::Custom { chain_name, .. } => chain_name.clone(),
         }
     }
-}
 
-impl From<Network> for String {
-    fn from(val: Network) -> Self {
-        match val {
+    pub fn api_url(&self) -> String {
+        match self {
             Network::Mainnet => "https://api.hyperliquid.xyz".to_string(),
             Network::Testnet => "https://api.hyperliquid-testnet.xyz".to_string(),
+            Network::Custom { api_url, .. } => api_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn ws_url(&self) -> String {
+        match self {
+            Network::Mainnet => "wss://api.hyperliquid.xyz/ws".to_string(),
+            Network::Testnet => "wss://api.hyperliquid-testnet.xyz/ws".to_string(),
+            Network::Custom { ws_url, .. } => ws_url.clone(),
+        }
+    }
+
+    pub fn is_mainnet(&self) -> bool {
+        match self {
+            Network::Mainnet => true,
+            Network::Testnet => false,
+            Network::Custom { is_mainnet, .. } => *is_mainnet,
         }
     }
 }
 
+impl From<Network> for String {
+    fn from(val: Network) -> Self {
+        val.api_url()
+    }
+}
+
 pub const MAX_SIGNIFICANT_DIGITS: i32 = 5i32;
 pub const MAX_DECIMALS_SPOT: i32 = 8i32;
 pub const MAX_DECIMALS_PERP: i32 = 6i32;
@@ -97,6 +132,74 @@ pub fn parse_chain_id(chain_id: &str) -> Result<u64> {
     .map_err(|e| anyhow::anyhow!("Invalid chain ID format: {}", e))
 }
 
+/// Most fills a single `userFillsByTime` response carries
+pub(crate) const FILLS_PAGE_CAP: usize = 2000;
+/// Most funding payments or ledger updates a single response carries. Erring low only costs
+/// an extra request per full page, erring high would skip items.
+pub(crate) const HISTORY_PAGE_CAP: usize = 500;
+
+/// Walks a time windowed info query forward from `start` until a page comes back empty or
+/// passes `end`. A page under `page_cap` items holds everything up to its newest item, so the
+/// next one starts 1ms later. A full page may have cut its newest millisecond short, so the
+/// next one starts at that millisecond again and skips the items `key_of` says were yielded.
+pub(crate) fn paginate_by_time<'a, T, K, F, Fut>(
+    start: u64,
+    end: Option<u64>,
+    page_cap: usize,
+    time_of: fn(&T) -> u64,
+    key_of: fn(&T) -> K,
+    fetch: F,
+) -> impl Stream<Item = Result<T>> + Send + 'a
+where
+    T: Send + 'a,
+    K: Eq + Hash + Send + 'a,
+    F: Fn(u64) -> Fut + Send + 'a,
+    Fut: Future<Output = Result<Vec<T>>> + Send + 'a,
+{
+    stream::try_unfold((Some(start), HashSet::new()), move |(cursor, seen)| {
+        let page = cursor.map(&fetch);
+        async move {
+            let (Some(cursor), Some(page)) = (cursor, page) else {
+                return Ok::<_, anyhow::Error>(None);
+            };
+            let page = page.await?;
+            let Some(newest) = page.iter().map(time_of).max() else {
+                return Ok(None);
+            };
+
+            let full = page.len() >= page_cap;
+            let boundary: HashSet<K> = page
+                .iter()
+                .filter(|item| time_of(item) == newest)
+                .map(key_of)
+                .collect();
+            let fresh: Vec<T> = page
+                .into_iter()
+                .filter(|item| time_of(item) != cursor || !seen.contains(&key_of(item)))
+                .collect();
+            if full && fresh.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "more than {} items at {}ms, the API cannot page past them",
+                    page_cap,
+                    newest
+                ));
+            }
+
+            let (next, seen) = if full {
+                (newest, boundary)
+            } else {
+                (newest + 1, HashSet::new())
+            };
+            let next =
+                Some(next).filter(|next| *next >= cursor && end.is_none_or(|end| *next <= end));
+            Ok(Some((fresh, (next, seen))))
+        }
+    })
+    .map_ok(|page| stream::iter(page.into_iter().map(Ok)))
+    .try_flatten()
+    .boxed()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,4 +211,76 @@ mod tests {
         println!("format test result: {}", result);
         Ok(())
     }
+
+    #[test]
+    fn test_custom_network() {
+        let network = Network::Custom {
+            api_url: "http://127.0.0.1:3001/".into(),
+            ws_url: "ws://127.0.0.1:3001/ws".into(),
+            chain_name: "Testnet".into(),
+            is_mainnet: false,
+        };
+        assert_eq!(network.api_url(), "http://127.0.0.1:3001");
+        assert_eq!(network.ws_url(), "ws://127.0.0.1:3001/ws");
+        assert_eq!(network.name(), Network::Testnet.name());
+        assert_eq!(network.is_mainnet(), Network::Testnet.is_mainnet());
+        assert_eq!(
+            String::from(Network::Mainnet),
+            "https://api.hyperliquid.xyz"
+        );
+    }
+
+    /// Serves `items` the way the info endpoint does: the oldest `cap` items in
+    /// `[start, end]`
+    fn page(items: &[(u64, char)], start: u64, end: Option<u64>, cap: usize) -> Vec<(u64, char)> {
+        items
+            .iter()
+            .copied()
+            .filter(|(t, _)| *t >= start && end.is_none_or(|end| *t <= end))
+            .take(cap)
+            .collect()
+    }
+
+    async fn collect(
+        items: &[(u64, char)],
+        end: Option<u64>,
+        cap: usize,
+    ) -> Result<Vec<(u64, char)>> {
+        paginate_by_time(
+            0,
+            end,
+            cap,
+            |item: &(u64, char)| item.0,
+            |item: &(u64, char)| item.1,
+            |start| {
+                let page = page(items, start, end, cap);
+                async move { Ok(page) }
+            },
+        )
+        .try_collect()
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_paginate_by_time() -> Result<()> {
+        let items: Vec<(u64, char)> = (0..7)
+            .map(|i| (100 + i * 10, (b'a' + i as u8) as char))
+            .collect();
+        assert_eq!(collect(&items, None, 3).await?, items);
+        assert_eq!(collect(&items, Some(125), 2).await?, items[..3]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_paginate_by_time_full_page_splits_a_millisecond() -> Result<()> {
+        // the first full page ends on c, cutting d off from the same millisecond
+        let items = vec![(100, 'a'), (100, 'b'), (110, 'c'), (110, 'd'), (120, 'e')];
+        assert_eq!(collect(&items, None, 3).await?, items);
+
+        // a millisecond holding more than a page can never be paged past
+        let crowded = vec![(100, 'a'), (110, 'b'), (110, 'c'), (110, 'd'), (120, 'e')];
+        let err = collect(&crowded, None, 3).await.unwrap_err();
+        assert!(err.to_string().contains("at 110ms"));
+        Ok(())
+    }
 }
@@ -43,4 +43,8 @@ impl WalletSigner for LocalWallet {
             v: signature.v() as u64 + 27,
         })
     }
+
+    fn address(&self) -> Option<Address> {
+        Some(self.wallet_key.address())
+    }
 }
@@ -0,0 +1,487 @@
+use std::pin::Pin;
+use std::time::Duration;
+
+use alloy::primitives::Address;
+use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender, unbounded};
+use futures::channel::oneshot;
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tokio_tungstenite::connect_async;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use crate::errors::*;
+use crate::internal::{ExchangeRequest, ExchangeResponse};
+use crate::market_info::{Candle, Interval, L2Book};
+use crate::utils::Network;
+use crate::ws::connection::*;
+use crate::ws::messages::*;
+
+pub type WsStream<T> = Pin<Box<dyn Stream<Item = Result<WsEvent<T>>> + Send>>;
+
+/// Push feed over the hyperliquid websocket. All subscriptions share one connection which is
+/// driven by a background task that reconnects and resubscribes on failure; dropping the client
+/// closes the connection and ends every stream.
+pub struct HyperliquidWsClient {
+    commands: UnboundedSender<WsCommand>,
+    order_updates_user: OrderUpdatesUser,
+    cancellation: CancellationToken,
+    post_timeout: Duration,
+}
+
+impl HyperliquidWsClient {
+    pub async fn connect(network: Network) -> Result<Self> {
+        Self::connect_url(&network.ws_url(), WsConfig::default()).await
+    }
+
+    pub async fn connect_url(url: &str, config: WsConfig) -> Result<Self> {
+        debug!("connecting websocket to {}", url);
+        let (socket, _) = connect_async(url).await?;
+
+        let (commands, command_rx) = unbounded();
+        let cancellation = CancellationToken::new();
+        let post_timeout = config.post_timeout;
+        let order_updates_user = OrderUpdatesUser::default();
+        tokio::spawn(run_connection(
+            url.to_string(),
+            config,
+            socket,
+            command_rx,
+            order_updates_user.clone(),
+            cancellation.clone(),
+        ));
+
+        Ok(HyperliquidWsClient {
+            commands,
+            order_updates_user,
+            cancellation,
+            post_timeout,
+        })
+    }
+
+    async fn post(&self, request: WsPostRequest) -> Result<WsPostPayload> {
+        let (responder, response) = oneshot::channel();
+        self.commands
+            .unbounded_send(WsCommand::Post { request, responder })
+            .map_err(|_| Errors::WebSocketError("connection closed".to_string()))?;
+
+        match tokio::time::timeout(self.post_timeout, response).await {
+            Ok(Ok(payload)) => Ok(payload),
+            Ok(Err(_)) => {
+                Err(Errors::WebSocketError("connection lost awaiting post".to_string()).into())
+            }
+            Err(_) => Err(Errors::WebSocketError(format!(
+                "post timed out after {:?}",
+                self.post_timeout
+            ))
+            .into()),
+        }
+    }
+
+    /// Sends an `/info` request body over the socket, the response is decoded exactly like the
+    /// http endpoint's body
+    pub async fn post_info<R: Serialize, T: DeserializeOwned>(&self, req: &R) -> Result<T> {
+        let payload = match self
+            .post(WsPostRequest::Info(serde_json::to_value(req)?))
+            .await?
+        {
+            WsPostPayload::Info(mut payload) => payload
+                .get_mut("data")
+                .map(serde_json::Value::take)
+                .unwrap_or(payload),
+            WsPostPayload::Error(e) => {
+                return Err(Errors::HyperLiquidApiError(100, e.to_string()).into());
+            }
+            WsPostPayload::Action(payload) => {
+                return Err(Errors::WebSocketError(format!(
+                    "unexpected action response to info post: {}",
+                    payload
+                ))
+                .into());
+            }
+        };
+        Ok(serde_json::from_value(payload)?)
+    }
+
+    /// Sends a signed `/exchange` payload over the socket
+    pub(crate) async fn post_action(&self, req: &ExchangeRequest) -> Result<ExchangeResponse> {
+        match self
+            .post(WsPostRequest::Action(serde_json::to_value(req)?))
+            .await?
+        {
+            WsPostPayload::Action(payload) => Ok(serde_json::from_value(payload)?),
+            WsPostPayload::Error(e) => Err(Errors::HyperLiquidApiError(100, e.to_string()).into()),
+            WsPostPayload::Info(payload) => Err(Errors::WebSocketError(format!(
+                "unexpected info response to action post: {}",
+                payload
+            ))
+            .into()),
+        }
+    }
+
+    /// Raw `data` payloads of every frame routed to `subscription`.
+    ///
+    /// Repeating a live subscription shares the server feed instead of subscribing again, so the
+    /// new stream starts at the next frame and never sees the snapshot (`isSnapshot` fills and
+    /// fundings) already delivered. Fetch current state through the info endpoint, or open a
+    /// separate connection, when a late stream needs it.
+    ///
+    /// `orderUpdates` frames do not name their user, so a connection carries the order updates
+    /// of one user at a time: subscribing another user fails until every stream of the first
+    /// one was dropped and its feed torn down, which happens by the next heartbeat. A conflict
+    /// only detected by the connection task ends the new stream with an `Err` item.
+    pub fn subscribe_raw(
+        &self,
+        subscription: Subscription,
+    ) -> Result<UnboundedReceiver<Result<WsEvent<serde_json::Value>>>> {
+        debug!("subscribing to {:?}", subscription);
+        if let Subscription::OrderUpdates { user } = &subscription {
+            let mut current = self.order_updates_user.lock().unwrap();
+            match current.as_ref() {
+                Some(live) if live != user => return Err(order_updates_taken(live)),
+                _ => *current = Some(user.clone()),
+            }
+        }
+
+        let (sender, receiver) = unbounded();
+        self.commands
+            .unbounded_send(WsCommand::Subscribe {
+                subscription,
+                sender,
+            })
+            .map_err(|_| Errors::WebSocketError("connection closed".to_string()))?;
+        Ok(receiver)
+    }
+
+    pub fn subscribe<T: DeserializeOwned + Send + 'static>(
+        &self,
+        subscription: Subscription,
+    ) -> Result<WsStream<T>> {
+        let receiver = self.subscribe_raw(subscription)?;
+        Ok(receiver
+            .map(|event| {
+                Ok(match event? {
+                    WsEvent::Data(data) => WsEvent::Data(serde_json::from_value(data)?),
+                    WsEvent::Gap { from, to } => WsEvent::Gap { from, to },
+                    WsEvent::Reconnected { attempts } => WsEvent::Reconnected { attempts },
+                    WsEvent::Closed { attempts } => WsEvent::Closed { attempts },
+                })
+            })
+            .boxed())
+    }
+
+    pub fn all_mids(&self) -> Result<WsStream<AllMids>> {
+        self.subscribe(Subscription::AllMids)
+    }
+
+    pub fn l2_book(&self, coin: &str) -> Result<WsStream<L2Book>> {
+        self.subscribe(Subscription::L2Book {
+            coin: coin.to_string(),
+        })
+    }
+
+    pub fn trades(&self, coin: &str) -> Result<WsStream<Vec<WsTrade>>> {
+        self.subscribe(Subscription::Trades {
+            coin: coin.to_string(),
+        })
+    }
+
+    pub fn candle(&self, coin: &str, interval: Interval) -> Result<WsStream<Candle>> {
+        self.subscribe(Subscription::Candle {
+            coin: coin.to_string(),
+            interval,
+        })
+    }
+
+    pub fn bbo(&self, coin: &str) -> Result<WsStream<WsBbo>> {
+        self.subscribe(Subscription::Bbo {
+            coin: coin.to_string(),
+        })
+    }
+
+    pub fn order_updates(&self, user: Address) -> Result<WsStream<Vec<WsOrderUpdate>>> {
+        self.subscribe(Subscription::OrderUpdates {
+            user: user.to_string(),
+        })
+    }
+
+    pub fn user_fills(&self, user: Address) -> Result<WsStream<WsUserFills>> {
+        self.subscribe(Subscription::UserFills {
+            user: user.to_string(),
+        })
+    }
+
+    pub fn user_fundings(&self, user: Address) -> Result<WsStream<WsUserFundings>> {
+        self.subscribe(Subscription::UserFundings {
+            user: user.to_string(),
+        })
+    }
+
+    pub fn web_data2(&self, user: Address) -> Result<WsStream<WebData2>> {
+        self.subscribe(Subscription::WebData2 {
+            user: user.to_string(),
+        })
+    }
+}
+
+impl Drop for HyperliquidWsClient {
+    fn drop(&mut self) {
+        self.cancellation.cancel();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::SinkExt;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+    // each accepted connection replays the next list of frames verbatim once the client sends its
+    // first request, every session but the last is then closed by the server
+    async fn replay_server(sessions: Vec<Vec<&'static str>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let total = sessions.len();
+            for (i, frames) in sessions.into_iter().enumerate() {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = accept_async(stream).await.unwrap();
+                let _ = ws.next().await;
+                for frame in frames {
+                    ws.send(Message::text(frame)).await.unwrap();
+                }
+                if i + 1 < total {
+                    ws.close(None).await.unwrap();
+                } else {
+                    while ws.next().await.is_some() {}
+                }
+            }
+        });
+        format!("ws://{}", addr)
+    }
+
+    fn test_config() -> WsConfig {
+        WsConfig {
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(50),
+            ..Default::default()
+        }
+    }
+
+    const BTC_BOOK: &str = r#"{"channel":"l2Book","data":{"coin":"BTC","time":1754450974232,"levels":[[{"px":"114363.0","sz":"0.01","n":1},{"px":"114362.0","sz":"2.3","n":4}],[{"px":"114364.0","sz":"1.1","n":3}]]}}"#;
+
+    #[tokio::test]
+    async fn test_l2_book_routing() {
+        let url = replay_server(vec![vec![
+            r#"{"channel":"subscriptionResponse","data":{"method":"subscribe","subscription":{"type":"l2Book","coin":"BTC"}}}"#,
+            r#"{"channel":"l2Book","data":{"coin":"ETH","time":1754450974231,"levels":[[{"px":"3601.1","sz":"1.2","n":2}],[{"px":"3601.2","sz":"0.4","n":1}]]}}"#,
+            BTC_BOOK,
+        ]])
+        .await;
+
+        let client = HyperliquidWsClient::connect_url(&url, test_config())
+            .await
+            .unwrap();
+        let mut books = client.l2_book("BTC").unwrap();
+
+        let WsEvent::Data(book) = books.next().await.unwrap().unwrap() else {
+            panic!("expected book data");
+        };
+        assert_eq!(book.coin, "BTC");
+        assert_eq!(book.levels[0].len(), 2);
+        assert_eq!(book.levels[1][0].px, "114364.0");
+    }
+
+    #[tokio::test]
+    async fn test_user_fills_snapshot() {
+        let url = replay_server(vec![vec![
+            r#"{"channel":"userFills","data":{"isSnapshot":true,"user":"0x5e9ee1089755c3435139848e47e6635505d5a13a","fills":[{"coin":"HYPE","px":"38.1","sz":"1.0","side":"B","time":1754450974231,"startPosition":"0.0","dir":"Open Long","closedPnl":"0.0","hash":"0x00","oid":77738308,"crossed":true,"fee":"0.017","tid":1,"feeToken":"USDC"}]}}"#,
+        ]])
+        .await;
+
+        let user: Address = "0x5e9ee1089755c3435139848e47e6635505d5a13a"
+            .parse()
+            .unwrap();
+        let client = HyperliquidWsClient::connect_url(&url, test_config())
+            .await
+            .unwrap();
+        let mut fills = client.user_fills(user).unwrap();
+
+        let WsEvent::Data(update) = fills.next().await.unwrap().unwrap() else {
+            panic!("expected fills data");
+        };
+        assert!(update.is_snapshot);
+        assert_eq!(update.fills.len(), 1);
+        assert_eq!(update.fills[0].oid, 77738308);
+        assert_eq!(update.fills[0].builder_fee, None);
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_resubscribes() {
+        let url = replay_server(vec![vec![BTC_BOOK], vec![BTC_BOOK]]).await;
+
+        let client = HyperliquidWsClient::connect_url(&url, test_config())
+            .await
+            .unwrap();
+        let mut books = client.l2_book("BTC").unwrap();
+
+        assert!(matches!(books.next().await, Some(Ok(WsEvent::Data(_)))));
+        assert!(matches!(books.next().await, Some(Ok(WsEvent::Gap { .. }))));
+        assert!(matches!(
+            books.next().await,
+            Some(Ok(WsEvent::Reconnected { attempts: 1 }))
+        ));
+        // the second session only answers once the subscription was replayed
+        assert!(matches!(books.next().await, Some(Ok(WsEvent::Data(_)))));
+    }
+
+    #[tokio::test]
+    async fn test_giving_up_reconnecting_closes_streams() {
+        // the server answers one session, then closes it and stops listening for good
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            let _ = ws.next().await;
+            ws.send(Message::text(BTC_BOOK)).await.unwrap();
+            ws.close(None).await.unwrap();
+        });
+
+        let config = WsConfig {
+            max_reconnect_attempts: Some(1),
+            ..test_config()
+        };
+        let client = HyperliquidWsClient::connect_url(&url, config)
+            .await
+            .unwrap();
+        let mut books = client.l2_book("BTC").unwrap();
+
+        assert!(matches!(books.next().await, Some(Ok(WsEvent::Data(_)))));
+        assert!(matches!(
+            books.next().await,
+            Some(Ok(WsEvent::Closed { attempts: 1 }))
+        ));
+        assert!(books.next().await.is_none());
+        assert!(client.l2_book("ETH").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_order_updates_are_exclusive_to_one_user() {
+        let url = replay_server(vec![vec![]]).await;
+        let client = HyperliquidWsClient::connect_url(&url, test_config())
+            .await
+            .unwrap();
+
+        let _first = client.order_updates(Address::repeat_byte(0x11)).unwrap();
+        let _same = client.order_updates(Address::repeat_byte(0x11)).unwrap();
+        let err = client
+            .order_updates(Address::repeat_byte(0x22))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("already live on this connection"));
+    }
+
+    #[tokio::test]
+    async fn test_dropped_order_updates_are_released_without_frames() {
+        let url = replay_server(vec![vec![]]).await;
+        let config = WsConfig {
+            heartbeat_interval: Duration::from_millis(20),
+            ..test_config()
+        };
+        let client = HyperliquidWsClient::connect_url(&url, config)
+            .await
+            .unwrap();
+
+        drop(client.order_updates(Address::repeat_byte(0x11)).unwrap());
+        // no frame ever arrives for the first user, the heartbeat sweep has to tear it down
+        let second = async {
+            loop {
+                match client.order_updates(Address::repeat_byte(0x22)) {
+                    Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+                    stream => break stream,
+                }
+            }
+        };
+        let _second = tokio::time::timeout(Duration::from_secs(5), second)
+            .await
+            .expect("first user's feed was never released")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_order_updates_end_with_an_error() {
+        let url = replay_server(vec![vec![]]).await;
+        let client = HyperliquidWsClient::connect_url(&url, test_config())
+            .await
+            .unwrap();
+        let _first = client.order_updates(Address::repeat_byte(0x11)).unwrap();
+
+        // bypass the client side check, as if the slot had been released and taken in between
+        let (sender, mut receiver) = unbounded();
+        client
+            .commands
+            .unbounded_send(WsCommand::Subscribe {
+                subscription: Subscription::OrderUpdates {
+                    user: Address::repeat_byte(0x22).to_string(),
+                },
+                sender,
+            })
+            .unwrap();
+
+        let err = receiver.next().await.unwrap().unwrap_err();
+        assert!(err.to_string().contains("already live on this connection"));
+        assert!(receiver.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_post_correlates_by_id() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            let mut posts = Vec::new();
+            while posts.len() < 2 {
+                let Some(Ok(Message::Text(text))) = ws.next().await else {
+                    continue;
+                };
+                let request: serde_json::Value = serde_json::from_str(text.as_str()).unwrap();
+                posts.push(request);
+            }
+            // answer out of order, each echoing the info type it was asked for
+            for post in posts.iter().rev() {
+                let response = serde_json::json!({
+                    "channel": "post",
+                    "data": {
+                        "id": post["id"],
+                        "response": {
+                            "type": "info",
+                            "payload": {
+                                "type": post["request"]["payload"]["type"],
+                                "data": post["request"]["payload"]["type"],
+                            }
+                        }
+                    }
+                });
+                ws.send(Message::text(response.to_string())).await.unwrap();
+            }
+            while ws.next().await.is_some() {}
+        });
+
+        let client = HyperliquidWsClient::connect_url(&url, test_config())
+            .await
+            .unwrap();
+        let mids_req = serde_json::json!({ "type": "allMids" });
+        let meta_req = serde_json::json!({ "type": "meta" });
+        let (mids, meta) = tokio::join!(
+            client.post_info::<_, String>(&mids_req),
+            client.post_info::<_, String>(&meta_req),
+        );
+        assert_eq!(mids.unwrap(), "allMids");
+        assert_eq!(meta.unwrap(), "meta");
+    }
+}
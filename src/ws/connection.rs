@@ -0,0 +1,456 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender};
+use futures::channel::oneshot;
+use futures::{SinkExt, StreamExt};
+use tokio::net::TcpStream;
+use tokio::time::{self, Instant};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite::Message};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+use crate::errors::*;
+use crate::ws::messages::*;
+
+pub(crate) type WsSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+type WsSink = futures::stream::SplitSink<WsSocket, Message>;
+
+/// User whose `orderUpdates` feed is live on the connection. Those frames carry no user to route
+/// by, so one connection can only serve a single user's order updates at a time.
+pub(crate) type OrderUpdatesUser = Arc<Mutex<Option<String>>>;
+
+/// Sending half of a subscription stream, failures reach the caller as `Err` items
+pub(crate) type EventSender = UnboundedSender<Result<WsEvent<serde_json::Value>>>;
+
+pub(crate) fn order_updates_taken(live: &str) -> anyhow::Error {
+    Errors::WebSocketError(format!(
+        "order updates of {} are already live on this connection",
+        live
+    ))
+    .into()
+}
+
+/// Item yielded by every subscription stream
+#[derive(Debug, Clone, PartialEq)]
+pub enum WsEvent<T> {
+    Data(T),
+    /// the connection dropped between `from` and `to` (unix ms), anything published in that
+    /// window was missed and local state should be re-snapshotted
+    Gap {
+        from: u64,
+        to: u64,
+    },
+    /// the connection was re-established and the subscription replayed, emitted right after `Gap`
+    Reconnected {
+        attempts: u32,
+    },
+    /// reconnecting was given up after `attempts` tries (see
+    /// [`WsConfig::max_reconnect_attempts`]), this is the last item of the stream
+    Closed {
+        attempts: u32,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct WsConfig {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// give up after this many consecutive failed reconnects, `None` retries forever
+    pub max_reconnect_attempts: Option<u32>,
+    /// how often a `ping` is sent to keep the connection alive
+    pub heartbeat_interval: Duration,
+    /// the connection is considered dead when nothing was received for this long
+    pub heartbeat_timeout: Duration,
+    /// how long a `post` waits for its response before failing
+    pub post_timeout: Duration,
+}
+
+impl Default for WsConfig {
+    fn default() -> Self {
+        WsConfig {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_reconnect_attempts: None,
+            heartbeat_interval: Duration::from_secs(20),
+            heartbeat_timeout: Duration::from_secs(60),
+            post_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl WsConfig {
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.min(16));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+pub(crate) enum WsCommand {
+    Subscribe {
+        subscription: Subscription,
+        sender: EventSender,
+    },
+    Post {
+        request: WsPostRequest,
+        responder: oneshot::Sender<WsPostPayload>,
+    },
+}
+
+struct ActiveSubscription {
+    subscription: Subscription,
+    senders: Vec<EventSender>,
+}
+
+impl ActiveSubscription {
+    /// the feed is being torn down, release the order updates slot if it held this user
+    fn release(&self, order_updates_user: &OrderUpdatesUser) {
+        if let Subscription::OrderUpdates { user } = &self.subscription {
+            let mut current = order_updates_user.lock().unwrap();
+            if current.as_ref() == Some(user) {
+                *current = None;
+            }
+        }
+    }
+}
+
+enum SessionEnd {
+    Shutdown,
+    Disconnected,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+async fn send_request(sink: &mut WsSink, request: &WsRequest) -> Result<()> {
+    let text = serde_json::to_string(request)?;
+    debug!("ws request: {}", text);
+    sink.send(Message::text(text)).await?;
+    Ok(())
+}
+
+/// Stops the feed of every subscription whose streams were all dropped. Feeds are otherwise only
+/// torn down when their next frame finds no receiver, which may never come for quiet channels.
+async fn sweep_closed(
+    sink: &mut WsSink,
+    active: &mut HashMap<String, ActiveSubscription>,
+    order_updates_user: &OrderUpdatesUser,
+) -> Result<()> {
+    let mut closed = vec![];
+    for (identifier, entry) in active.iter_mut() {
+        entry.senders.retain(|sender| !sender.is_closed());
+        if entry.senders.is_empty() {
+            closed.push(identifier.clone());
+        }
+    }
+
+    for identifier in closed {
+        if let Some(entry) = active.remove(&identifier) {
+            entry.release(order_updates_user);
+            let request = WsRequest::Unsubscribe {
+                subscription: entry.subscription,
+            };
+            send_request(sink, &request).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Owns the socket for the lifetime of a client: drives one session at a time and, whenever a
+/// session drops, reconnects with exponential backoff and replays every active subscription.
+pub(crate) async fn run_connection(
+    url: String,
+    config: WsConfig,
+    socket: WsSocket,
+    mut commands: UnboundedReceiver<WsCommand>,
+    order_updates_user: OrderUpdatesUser,
+    cancellation: CancellationToken,
+) {
+    let mut active: HashMap<String, ActiveSubscription> = HashMap::new();
+    let mut next_post_id = 0u64;
+    let mut socket = socket;
+
+    loop {
+        let session = run_session(
+            socket,
+            &config,
+            &mut active,
+            &mut next_post_id,
+            &mut commands,
+            &order_updates_user,
+            &cancellation,
+        );
+        match session.await {
+            SessionEnd::Shutdown => return,
+            SessionEnd::Disconnected => {}
+        }
+
+        let disconnected_at = now_ms();
+        let mut attempts = 0u32;
+        socket = loop {
+            let backoff = config.backoff(attempts);
+            attempts += 1;
+            warn!(
+                "websocket disconnected, reconnect attempt {} in {:?}",
+                attempts, backoff
+            );
+
+            tokio::select! {
+                _ = cancellation.cancelled() => return,
+                _ = time::sleep(backoff) => {}
+            }
+
+            match connect_async(url.as_str()).await {
+                Ok((socket, _)) => break socket,
+                Err(e) => {
+                    error!("websocket reconnect failed: {}", e);
+                    if config
+                        .max_reconnect_attempts
+                        .is_some_and(|max| attempts >= max)
+                    {
+                        error!("giving up on websocket after {} attempts", attempts);
+                        for entry in active.values() {
+                            for sender in &entry.senders {
+                                let _ = sender.unbounded_send(Ok(WsEvent::Closed { attempts }));
+                            }
+                        }
+                        return;
+                    }
+                }
+            }
+        };
+
+        info!("websocket reconnected after {} attempts", attempts);
+        let gap = WsEvent::Gap {
+            from: disconnected_at,
+            to: now_ms(),
+        };
+        for entry in active.values_mut() {
+            entry.senders.retain(|sender| {
+                sender.unbounded_send(Ok(gap.clone())).is_ok()
+                    && sender
+                        .unbounded_send(Ok(WsEvent::Reconnected { attempts }))
+                        .is_ok()
+            });
+        }
+        active.retain(|_, entry| {
+            if entry.senders.is_empty() {
+                entry.release(&order_updates_user);
+            }
+            !entry.senders.is_empty()
+        });
+    }
+}
+
+async fn run_session(
+    socket: WsSocket,
+    config: &WsConfig,
+    active: &mut HashMap<String, ActiveSubscription>,
+    next_post_id: &mut u64,
+    commands: &mut UnboundedReceiver<WsCommand>,
+    order_updates_user: &OrderUpdatesUser,
+    cancellation: &CancellationToken,
+) -> SessionEnd {
+    let (mut sink, mut stream) = socket.split();
+    // posts still in flight when the session ends are dropped, failing their callers
+    let mut pending: HashMap<u64, oneshot::Sender<WsPostPayload>> = HashMap::new();
+
+    // replay everything that was live on the previous session
+    for entry in active.values() {
+        let request = WsRequest::Subscribe {
+            subscription: entry.subscription.clone(),
+        };
+        if let Err(e) = send_request(&mut sink, &request).await {
+            error!("failed to resubscribe: {}", e);
+            return SessionEnd::Disconnected;
+        }
+    }
+
+    let mut heartbeat = time::interval_at(
+        Instant::now() + config.heartbeat_interval,
+        config.heartbeat_interval,
+    );
+    let mut last_seen = Instant::now();
+
+    loop {
+        tokio::select! {
+            _ = cancellation.cancelled() => {
+                debug!("closing websocket connection");
+                let _ = sink.close().await;
+                return SessionEnd::Shutdown;
+            }
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() >= config.heartbeat_timeout {
+                    warn!("websocket heartbeat timed out after {:?}", last_seen.elapsed());
+                    return SessionEnd::Disconnected;
+                }
+                if let Err(e) = send_request(&mut sink, &WsRequest::Ping).await {
+                    error!("failed to send ping: {}", e);
+                    return SessionEnd::Disconnected;
+                }
+                if let Err(e) = sweep_closed(&mut sink, active, order_updates_user).await {
+                    error!("failed to unsubscribe: {}", e);
+                    return SessionEnd::Disconnected;
+                }
+            }
+            command = commands.next() => {
+                let (subscription, sender) = match command {
+                    Some(WsCommand::Subscribe { subscription, sender }) => (subscription, sender),
+                    Some(WsCommand::Post { request, responder }) => {
+                        *next_post_id += 1;
+                        let id = *next_post_id;
+                        pending.insert(id, responder);
+                        if let Err(e) = send_request(&mut sink, &WsRequest::Post { id, request }).await {
+                            error!("failed to send post {}: {}", id, e);
+                            return SessionEnd::Disconnected;
+                        }
+                        continue;
+                    }
+                    None => {
+                        let _ = sink.close().await;
+                        return SessionEnd::Shutdown;
+                    }
+                };
+
+                // only order updates of another user share an identifier, the client rejects
+                // those up front but a feed released and re-taken concurrently can still race
+                // here. The live feed may just be waiting for its teardown, so sweep first.
+                let identifier = subscription.identifier();
+                if active.get(&identifier).is_some_and(|entry| entry.subscription != subscription)
+                    && let Err(e) = sweep_closed(&mut sink, active, order_updates_user).await
+                {
+                    error!("failed to unsubscribe: {}", e);
+                    return SessionEnd::Disconnected;
+                }
+                let entry = active
+                    .entry(identifier)
+                    .or_insert_with(|| ActiveSubscription {
+                        subscription: subscription.clone(),
+                        senders: Vec::new(),
+                    });
+                if let Subscription::OrderUpdates { user } = &entry.subscription
+                    && entry.subscription != subscription
+                {
+                    warn!("{:?} conflicts with live {:?}", subscription, entry.subscription);
+                    let _ = sender.unbounded_send(Err(order_updates_taken(user)));
+                    continue;
+                }
+                if let Subscription::OrderUpdates { user } = &subscription {
+                    *order_updates_user.lock().unwrap() = Some(user.clone());
+                }
+                entry.senders.push(sender);
+
+                // only the first subscriber of an identifier goes to the wire
+                if entry.senders.len() == 1 {
+                    let request = WsRequest::Subscribe { subscription };
+                    if let Err(e) = send_request(&mut sink, &request).await {
+                        error!("failed to subscribe: {}", e);
+                        return SessionEnd::Disconnected;
+                    }
+                }
+            }
+            message = stream.next() => {
+                last_seen = Instant::now();
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(frame))) => {
+                        warn!("websocket closed by server: {:?}", frame);
+                        return SessionEnd::Disconnected;
+                    }
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => {
+                        error!("websocket read failed: {}", e);
+                        return SessionEnd::Disconnected;
+                    }
+                    None => return SessionEnd::Disconnected,
+                };
+
+                let frame: WsFrame = match serde_json::from_str(text.as_str()) {
+                    Ok(frame) => frame,
+                    Err(e) => {
+                        warn!("failed to parse ws frame: {} - {}", e, text.as_str());
+                        continue;
+                    }
+                };
+
+                match frame.channel.as_str() {
+                    "subscriptionResponse" | "pong" => {
+                        debug!("ws {}: {}", frame.channel, frame.data);
+                        continue;
+                    }
+                    "error" => {
+                        error!("ws error: {}", frame.data);
+                        continue;
+                    }
+                    "post" => {
+                        match serde_json::from_value::<WsPostResponse>(frame.data) {
+                            Ok(post) => match pending.remove(&post.id) {
+                                Some(responder) => {
+                                    let _ = responder.send(post.response);
+                                }
+                                None => debug!("response for unknown post {}", post.id),
+                            },
+                            Err(e) => warn!("failed to parse post response: {}", e),
+                        }
+                        continue;
+                    }
+                    _ => {}
+                }
+
+                let Some(identifier) = frame.identifier() else {
+                    debug!("unrouted ws frame on channel {}", frame.channel);
+                    continue;
+                };
+                let Some(entry) = active.get_mut(&identifier) else {
+                    continue;
+                };
+
+                entry.senders.retain(|sender| {
+                    sender
+                        .unbounded_send(Ok(WsEvent::Data(frame.data.clone())))
+                        .is_ok()
+                });
+
+                // every stream for this identifier was dropped, stop the feed upstream
+                if entry.senders.is_empty()
+                    && let Some(entry) = active.remove(&identifier)
+                {
+                    entry.release(order_updates_user);
+                    let request = WsRequest::Unsubscribe {
+                        subscription: entry.subscription,
+                    };
+                    if let Err(e) = send_request(&mut sink, &request).await {
+                        error!("failed to unsubscribe: {}", e);
+                        return SessionEnd::Disconnected;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let config = WsConfig {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            ..Default::default()
+        };
+        assert_eq!(config.backoff(0), Duration::from_millis(100));
+        assert_eq!(config.backoff(3), Duration::from_millis(800));
+        assert_eq!(config.backoff(4), Duration::from_secs(1));
+        assert_eq!(config.backoff(64), Duration::from_secs(1));
+    }
+}
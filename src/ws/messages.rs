@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::market_info::{Interval, Level, MarketData, PerpetualMetadata, UniverseInfo};
+use crate::user_data::{Fills, OpenOrder, OrderState, UserPerpPosition, UserSpotPosition};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Subscription {
+    AllMids,
+    L2Book { coin: String },
+    Trades { coin: String },
+    Candle { coin: String, interval: Interval },
+    Bbo { coin: String },
+    OrderUpdates { user: String },
+    UserFills { user: String },
+    UserFundings { user: String },
+    WebData2 { user: String },
+}
+
+impl Subscription {
+    /// key used to route incoming frames back to the subscription that requested them,
+    /// must agree with `WsFrame::identifier`
+    pub(crate) fn identifier(&self) -> String {
+        match self {
+            Subscription::AllMids => "allMids".to_string(),
+            Subscription::L2Book { coin } => format!("l2Book:{}", coin.to_lowercase()),
+            Subscription::Trades { coin } => format!("trades:{}", coin.to_lowercase()),
+            Subscription::Candle { coin, interval } => {
+                format!("candle:{},{}", coin.to_lowercase(), interval)
+            }
+            Subscription::Bbo { coin } => format!("bbo:{}", coin.to_lowercase()),
+            Subscription::OrderUpdates { .. } => "orderUpdates".to_string(),
+            Subscription::UserFills { user } => format!("userFills:{}", user.to_lowercase()),
+            Subscription::UserFundings { user } => {
+                format!("userFundings:{}", user.to_lowercase())
+            }
+            Subscription::WebData2 { user } => format!("webData2:{}", user.to_lowercase()),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "method", rename_all = "camelCase")]
+pub(crate) enum WsRequest {
+    Subscribe { subscription: Subscription },
+    Unsubscribe { subscription: Subscription },
+    Ping,
+    Post { id: u64, request: WsPostRequest },
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", content = "payload", rename_all = "camelCase")]
+pub(crate) enum WsPostRequest {
+    Info(serde_json::Value),
+    Action(serde_json::Value),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", content = "payload", rename_all = "camelCase")]
+pub(crate) enum WsPostPayload {
+    Info(serde_json::Value),
+    Action(serde_json::Value),
+    Error(serde_json::Value),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct WsPostResponse {
+    pub id: u64,
+    pub response: WsPostPayload,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct WsFrame {
+    pub channel: String,
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+impl WsFrame {
+    pub(crate) fn identifier(&self) -> Option<String> {
+        let lower = |v: &serde_json::Value| v.as_str().map(|s| s.to_lowercase());
+        match self.channel.as_str() {
+            "allMids" => Some("allMids".to_string()),
+            "l2Book" | "bbo" => {
+                let coin = lower(self.data.get("coin")?)?;
+                Some(format!("{}:{}", self.channel, coin))
+            }
+            "trades" => {
+                let coin = lower(self.data.as_array()?.first()?.get("coin")?)?;
+                Some(format!("trades:{}", coin))
+            }
+            "candle" => {
+                let coin = lower(self.data.get("s")?)?;
+                let interval = self.data.get("i")?.as_str()?;
+                Some(format!("candle:{},{}", coin, interval))
+            }
+            "orderUpdates" => Some("orderUpdates".to_string()),
+            "userFills" | "userFundings" | "webData2" => {
+                let user = lower(self.data.get("user")?)?;
+                Some(format!("{}:{}", self.channel, user))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AllMids {
+    pub mids: HashMap<String, String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsTrade {
+    pub coin: String,
+    pub side: String,
+    pub px: String,
+    pub sz: String,
+    pub hash: String,
+    pub time: i64,
+    pub tid: i64,
+    pub users: [String; 2],
+}
+
+/// bbo is `[bid, ask]`, either side is absent when that side of the book is empty
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsBbo {
+    pub coin: String,
+    pub time: u64,
+    pub bbo: [Option<Level>; 2],
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsBasicOrder {
+    pub coin: String,
+    pub side: String,
+    pub limit_px: String,
+    pub sz: String,
+    pub oid: i64,
+    pub timestamp: i64,
+    pub orig_sz: String,
+    pub cloid: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsOrderUpdate {
+    pub order: WsBasicOrder,
+    pub status: OrderState,
+    pub status_timestamp: i64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsUserFills {
+    #[serde(default)]
+    pub is_snapshot: bool,
+    pub user: String,
+    pub fills: Vec<Fills>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsUserFunding {
+    pub time: i64,
+    pub coin: String,
+    pub usdc: String,
+    pub szi: String,
+    pub funding_rate: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsUserFundings {
+    #[serde(default)]
+    pub is_snapshot: bool,
+    pub user: String,
+    pub fundings: Vec<WsUserFunding>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebData2 {
+    pub user: String,
+    pub clearinghouse_state: UserPerpPosition,
+    pub open_orders: Vec<OpenOrder>,
+    pub meta: UniverseInfo,
+    pub asset_ctxs: Vec<PerpetualMetadata>,
+    pub server_time: i64,
+    #[serde(default)]
+    pub is_vault: bool,
+    pub agent_address: Option<String>,
+    pub agent_valid_until: Option<i64>,
+    pub cum_ledger: Option<String>,
+    pub total_vault_equity: Option<String>,
+    pub spot_state: Option<UserSpotPosition>,
+    pub spot_asset_ctxs: Option<Vec<MarketData>>,
+}
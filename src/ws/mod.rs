@@ -0,0 +1,7 @@
+mod client;
+mod connection;
+mod messages;
+
+pub use client::*;
+pub use connection::{WsConfig, WsEvent};
+pub use messages::*;